@@ -3,7 +3,7 @@ use std::borrow::Borrow;
 use crate::arith::EvalError::{NoRule, NoElse};
 use std::fmt;
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum Term {
     True,
     False,
@@ -19,7 +19,8 @@ impl fmt::Debug for Term {
         match self.clone() {
             Term::True => write!(f, "True"),
             Term::False => write!(f, "False"),
-            Term::If(ref term1, ref term2, ref term3) => write!(f, "(If {:?} {:?} {:?})", term1, term2, term3.as_ref().unwrap()),
+            Term::If(ref term1, ref term2, Some(ref term3)) => write!(f, "(If {:?} {:?} {:?})", term1, term2, term3),
+            Term::If(ref term1, ref term2, None) => write!(f, "(If {:?} {:?})", term1, term2),
             Term::Zero => write!(f, "Zero"),
             Term::Succ(ref term1) => write!(f, "(Succ {:?})", term1),
             Term::Pred(ref term1) => write!(f, "(Pred {:?})", term1),
@@ -28,7 +29,67 @@ impl fmt::Debug for Term {
     }
 }
 
+// Numeric values are printed as decimal literals. Arguments of `succ`,
+// `pred` and `iszero` are parenthesized unless they are constants or
+// literals, and the parts of an `if` when they are `if`s themselves,
+// so an inner `if` never takes an outer `else`.
+fn write_sub(f: &mut fmt::Formatter, t: &Term, wrap: bool) -> fmt::Result {
+    if wrap {
+        write!(f, "({})", t)
+    } else {
+        write!(f, "{}", t)
+    }
+}
+
+fn numeral(t: &Term) -> Option<u64> {
+    match t {
+        Term::Zero => Some(0),
+        Term::Succ(term1) => numeral(term1).map(|n| n + 1),
+        _ => None,
+    }
+}
+
+/// Prints the concrete syntax the parser reads back as the same term.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let is_const = |t: &Term| matches!(t, Term::True | Term::False) || numeral(t).is_some();
+        let is_if = |t: &Term| matches!(t, Term::If(_, _, _));
+        if let Some(n) = numeral(self) {
+            return write!(f, "{}", n);
+        }
+        match self {
+            Term::True => write!(f, "true"),
+            Term::False => write!(f, "false"),
+            Term::Zero => write!(f, "0"),
+            Term::Succ(term1) => {
+                write!(f, "succ ")?;
+                write_sub(f, term1, !is_const(term1))
+            }
+            Term::Pred(term1) => {
+                write!(f, "pred ")?;
+                write_sub(f, term1, !is_const(term1))
+            }
+            Term::IsZero(term1) => {
+                write!(f, "iszero ")?;
+                write_sub(f, term1, !is_const(term1))
+            }
+            Term::If(term1, term2, term3) => {
+                write!(f, "if ")?;
+                write_sub(f, term1, is_if(term1))?;
+                write!(f, " then ")?;
+                write_sub(f, term2, is_if(term2))?;
+                if let Some(term3) = term3 {
+                    write!(f, " else ")?;
+                    write_sub(f, term3, is_if(term3))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 
+#[derive(Clone, PartialEq)]
 pub enum EvalError {
     NoRule(Term),
     NoElse(Term)
@@ -44,7 +105,7 @@ impl fmt::Debug for EvalError {
     }
 }
 
-fn is_numeric_val(t: &Term) -> bool {
+pub(crate) fn is_numeric_val(t: &Term) -> bool {
     match t.clone() {
         Term::Zero => true,
         Succ(ref term1) => is_numeric_val((*term1).as_ref()),
@@ -52,7 +113,7 @@ fn is_numeric_val(t: &Term) -> bool {
     }
 }
 
-fn eval1(term: Term) -> Result<Term, EvalError> {
+pub fn eval1(term: Term) -> Result<Term, EvalError> {
     match term {
         Term::If(term1, term2, term3) => {
             match *term1 {
@@ -101,10 +162,8 @@ fn eval1(term: Term) -> Result<Term, EvalError> {
             }
         }
         Term::Succ(term1) => {
-            match *term1 {
-                Term::Pred(term2) => Ok(Term::Succ(Box::from(eval1(Term::Pred(term2)).unwrap()))),
-                _ => Err(EvalError::NoRule(*term1))
-            }
+            let tmp_term = Term::Succ(Box::from(eval1(*term1)?));
+            Ok(tmp_term)
         }
         term1 => Err(EvalError::NoRule(term1))
     }
@@ -120,6 +179,111 @@ pub fn eval(term: Term) -> Term {
 }
 
 
+// The evaluation context around the subterm `eval_iterative` works on: one
+// frame per enclosing term, holding the parts not yet evaluated.
+enum Frame {
+    If(Box<Term>, Option<Box<Term>>),
+    Succ,
+    Pred,
+    IsZero,
+}
+
+impl Frame {
+    fn plug(self, term: Term) -> Term {
+        let term = Box::new(term);
+        match self {
+            Frame::If(term2, term3) => Term::If(term, term2, term3),
+            Frame::Succ => Term::Succ(term),
+            Frame::Pred => Term::Pred(term),
+            Frame::IsZero => Term::IsZero(term),
+        }
+    }
+}
+
+// Drops a term without recursing, so discarding a deep branch is as
+// stack-safe as evaluating it.
+fn dispose(term: Term) {
+    let mut stack = vec![term];
+    while let Some(term) = stack.pop() {
+        match term {
+            Term::Succ(term1) | Term::Pred(term1) | Term::IsZero(term1) => stack.push(*term1),
+            Term::If(term1, term2, term3) => {
+                stack.push(*term1);
+                stack.push(*term2);
+                if let Some(term3) = term3 {
+                    stack.push(*term3);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Computes the same normal form as `eval`, but keeps the evaluation
+/// context in a heap-allocated stack of frames instead of recursing, so
+/// deeply nested terms cannot overflow the Rust stack.
+pub fn eval_iterative(term: Term) -> Term {
+    let mut stack: Vec<Frame> = vec![];
+    let mut focus = term;
+    loop {
+        // Go down to the leftmost subterm in evaluation position.
+        loop {
+            let (frame, term1) = match focus {
+                Term::If(term1, term2, term3) => (Frame::If(term2, term3), term1),
+                Term::Succ(term1) => (Frame::Succ, term1),
+                Term::Pred(term1) => (Frame::Pred, term1),
+                Term::IsZero(term1) => (Frame::IsZero, term1),
+                value => {
+                    focus = value;
+                    break;
+                }
+            };
+            stack.push(frame);
+            focus = *term1;
+        }
+
+        // `focus` is a value now. Go back up, contracting the redexes it
+        // completes, until an `if` has a branch left to evaluate.
+        loop {
+            let frame = match stack.pop() {
+                Some(frame) => frame,
+                None => return focus,
+            };
+            match (frame, focus) {
+                (Frame::If(term2, term3), Term::True) => {
+                    if let Some(term3) = term3 {
+                        dispose(*term3);
+                    }
+                    focus = *term2;
+                    break;
+                }
+                (Frame::If(term2, Some(term3)), Term::False) => {
+                    dispose(*term2);
+                    focus = *term3;
+                    break;
+                }
+                (Frame::Succ, value @ Term::Zero) | (Frame::Succ, value @ Term::Succ(_)) => focus = Term::Succ(Box::new(value)),
+                (Frame::Pred, Term::Zero) => focus = Term::Zero,
+                (Frame::Pred, Term::Succ(term1)) => focus = *term1,
+                (Frame::IsZero, Term::Zero) => focus = Term::True,
+                (Frame::IsZero, value @ Term::Succ(_)) => {
+                    dispose(value);
+                    focus = Term::False;
+                }
+                // No rule applies: the whole term is stuck.
+                (frame, value) => {
+                    let mut term = frame.plug(value);
+                    while let Some(frame) = stack.pop() {
+                        term = frame.plug(term);
+                    }
+                    return term;
+                }
+            }
+        }
+    }
+}
+
+
 fn is_val(t: &Term) -> bool {
     match t.clone() {
         Term::True | Term::False => true,
@@ -129,65 +293,155 @@ fn is_val(t: &Term) -> bool {
 }
 
 
-pub fn big_eval(term: Term) -> Term {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StuckReason {
+    NotNumeric,
+    NotBoolean,
+    NoElse,
+}
+
+impl fmt::Display for StuckReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StuckReason::NotNumeric => write!(f, "expected a numeric value"),
+            StuckReason::NotBoolean => write!(f, "expected a boolean value"),
+            StuckReason::NoElse => write!(f, "the condition is false and there is no else branch"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Classification {
+    Numeric(Term),
+    Boolean(Term),
+    // `subterm` is the innermost part of `normal_form` no rule applies to.
+    Stuck { normal_form: Term, subterm: Term, reason: StuckReason },
+}
+
+// Follows the evaluation contexts of a normal form that is not a value
+// down to the subterm that stops evaluation.
+fn stuck_subterm(t: &Term) -> (Term, StuckReason) {
+    match t {
+        Term::If(term1, _, term3) => match **term1 {
+            Term::False if term3.is_none() => (t.clone(), StuckReason::NoElse),
+            ref term1 if is_val(term1) => (t.clone(), StuckReason::NotBoolean),
+            ref term1 => stuck_subterm(term1),
+        },
+        Term::Succ(term1) | Term::Pred(term1) | Term::IsZero(term1) => {
+            if is_val(term1) {
+                (t.clone(), StuckReason::NotNumeric)
+            } else {
+                stuck_subterm(term1)
+            }
+        }
+        _ => unreachable!("values are not stuck"),
+    }
+}
+
+/// Evaluates `term` to its normal form and tells whether that is a value
+/// or a stuck term, a runtime error in TAPL's sense.
+pub fn classify(term: Term) -> Classification {
+    classify_normal_form(eval(term))
+}
+
+fn classify_normal_form(normal_form: Term) -> Classification {
+    match normal_form {
+        Term::True | Term::False => Classification::Boolean(normal_form),
+        ref t if is_numeric_val(t) => Classification::Numeric(normal_form),
+        _ => {
+            let (subterm, reason) = stuck_subterm(&normal_form);
+            Classification::Stuck { normal_form, subterm, reason }
+        }
+    }
+}
+
+
+/// An item of `steps`: a term evaluation passes through, or how it ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Term(Term),
+    Done(Classification),
+}
+
+/// The iterator returned by `steps`.
+pub struct Steps {
+    term: Option<Term>,
+}
+
+impl Iterator for Steps {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        let term = self.term.take()?;
+        match eval1(term.clone()) {
+            Ok(next) => {
+                self.term = Some(next);
+                Some(Step::Term(term))
+            }
+            Err(_) => Some(Step::Done(classify_normal_form(term))),
+        }
+    }
+}
+
+/// Every term small-step evaluation of `term` passes through, starting
+/// with `term` itself, followed by the normal form classified as a value
+/// or a stuck term.
+pub fn steps(term: Term) -> Steps {
+    Steps { term: Some(term) }
+}
+
+
+/// The big-step semantics of TAPL exercise 3.5.17. Stuck terms have no
+/// derivation; the error names the subterm where evaluation stops.
+pub fn big_eval(term: Term) -> Result<Term, EvalError> {
     if is_val(term.borrow()) {
-        return term;
+        return Ok(term);
     }
 
     match term {
         Term::If(term1, term2, term3) => {
-            match *term1 {
+            match big_eval(*term1)? {
                 Term::True => {
                     big_eval(*term2)
                 }
                 Term::False => {
-                    if term3 != None {
-                        big_eval(*term3.unwrap())
-                    } else {
-                        panic!("Err~~");
+                    match term3 {
+                        Some(term3) => big_eval(*term3),
+                        None => Err(EvalError::NoElse(Term::If(Box::new(Term::False), term2, None))),
                     }
                 }
-                _ => {
-                    let tmp_term = Term::If(Box::new(big_eval(*term1)), term2, term3);
-                    big_eval(tmp_term)
-                }
+                term1 => Err(EvalError::NoRule(Term::If(Box::new(term1), term2, term3))),
             }
         }
         Term::IsZero(term1) => {
-            match *term1 {
+            match big_eval(*term1)? {
                 Term::Zero => {
-                    Term::True
-                }
-                Term::Succ(term2) if is_numeric_val((*term2).borrow()) => {
-                    Term::False
+                    Ok(Term::True)
                 }
-                _ => {
-                    let tmp_term = Term::IsZero(Box::from(big_eval(*term1)));
-                    big_eval(tmp_term)
+                Term::Succ(_) => {
+                    Ok(Term::False)
                 }
+                term1 => Err(EvalError::NoRule(Term::IsZero(Box::new(term1)))),
             }
         }
         Term::Pred(term1) => {
-            match *term1 {
+            match big_eval(*term1)? {
                 Term::Zero => {
-                    Term::Zero
-                }
-                Term::Succ(term2) if is_numeric_val((*term2).borrow())  => {
-                    big_eval(*term2)
+                    Ok(Term::Zero)
                 }
-                _ => {
-                    let tmp_term = Term::Pred(Box::from(big_eval(*term1)));
-                    big_eval(tmp_term)
+                Term::Succ(term2) => {
+                    Ok(*term2)
                 }
+                term1 => Err(EvalError::NoRule(Term::Pred(Box::new(term1)))),
             }
         }
         Term::Succ(term1) => {
-            match *term1 {
-                Term::Pred(term2) => Term::Succ(Box::from(big_eval(Term::Pred(term2)))),
-                _ => *term1
+            match big_eval(*term1)? {
+                term1 if is_numeric_val(&term1) => Ok(Term::Succ(Box::new(term1))),
+                term1 => Err(EvalError::NoRule(Term::Succ(Box::new(term1)))),
             }
         }
-        term1 => term1
+        term1 => Err(EvalError::NoRule(term1))
     }
 }
 
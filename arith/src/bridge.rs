@@ -0,0 +1,74 @@
+use tyarith::derivation::{derive, TypeError};
+use tyarith::tyarith::{self as typed, Type, MAX_CHAIN_LITERAL};
+use crate::arith::{classify, Classification, Term};
+use crate::generator::terms;
+
+impl From<&Term> for typed::Term {
+    fn from(t: &Term) -> Self {
+        match t {
+            Term::True => typed::Term::True,
+            Term::False => typed::Term::False,
+            Term::Zero => typed::Term::Zero,
+            Term::IsZero(t1) => typed::Term::IsZero(Box::new(typed::Term::from(&**t1))),
+            Term::If(t1, t2, t3) => typed::Term::If(
+                Box::new(typed::Term::from(&**t1)),
+                Box::new(typed::Term::from(&**t2)),
+                t3.as_ref().map(|t3| Box::new(typed::Term::from(&**t3))),
+            ),
+            Term::Succ(t1) => typed::Term::Succ(Box::new(typed::Term::from(&**t1))),
+            Term::Pred(t1) => typed::Term::Pred(Box::new(typed::Term::from(&**t1))),
+        }
+    }
+}
+
+/// The arith term a tyarith term stands for, with literals expanded to
+/// `succ` chains, or `None` if it uses a primitive arith does not have or
+/// a literal above `MAX_CHAIN_LITERAL`, too long a chain to build.
+pub fn from_tyarith(t: &typed::Term) -> Option<Term> {
+    Some(match t {
+        typed::Term::True => Term::True,
+        typed::Term::False => Term::False,
+        typed::Term::Zero => Term::Zero,
+        typed::Term::Num(n) if *n > MAX_CHAIN_LITERAL => return None,
+        typed::Term::Num(n) => (0..*n).fold(Term::Zero, |t, _| Term::Succ(Box::new(t))),
+        typed::Term::IsZero(t1) => Term::IsZero(Box::new(from_tyarith(t1)?)),
+        typed::Term::If(t1, t2, t3) => {
+            let t3 = match t3 {
+                Some(t3) => Some(Box::new(from_tyarith(t3)?)),
+                None => None,
+            };
+            Term::If(Box::new(from_tyarith(t1)?), Box::new(from_tyarith(t2)?), t3)
+        }
+        typed::Term::Succ(t1) => Term::Succ(Box::new(from_tyarith(t1)?)),
+        typed::Term::Pred(t1) => Term::Pred(Box::new(from_tyarith(t1)?)),
+        typed::Term::Prim(_, _, _) => return None,
+    })
+}
+
+/// The type tyarith gives `t`, or the first type error in it.
+pub fn type_of(t: &Term) -> Result<Type, TypeError> {
+    derive(&typed::Term::from(t)).map(|d| d.ty)
+}
+
+/// A term that evaluates to a value but is rejected by the type checker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Witness {
+    pub term: Term,
+    pub value: Term,
+    pub error: TypeError,
+}
+
+/// Checks whether `t` shows that typing is incomplete (TAPL exercise
+/// 8.3.x): it evaluates to a value, yet is ill-typed.
+pub fn incompleteness_witness(t: &Term) -> Option<Witness> {
+    let error = type_of(t).err()?;
+    match classify(t.clone()) {
+        Classification::Numeric(value) | Classification::Boolean(value) => Some(Witness { term: t.clone(), value, error }),
+        Classification::Stuck { .. } => None,
+    }
+}
+
+/// Every witness of incompleteness among the terms of `S_depth`.
+pub fn incompleteness_witnesses(depth: usize) -> Vec<Witness> {
+    terms(depth).iter().filter_map(incompleteness_witness).collect()
+}
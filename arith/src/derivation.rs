@@ -0,0 +1,145 @@
+use std::fmt;
+use crate::arith::{is_numeric_val, EvalError, Term};
+
+/// The evaluation rules of TAPL figures 3-1 and 3-2.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    IfTrue,
+    IfFalse,
+    If,
+    Succ,
+    PredZero,
+    PredSucc,
+    Pred,
+    IsZeroZero,
+    IsZeroSucc,
+    IsZero,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Rule::IfTrue => "E-IfTrue",
+            Rule::IfFalse => "E-IfFalse",
+            Rule::If => "E-If",
+            Rule::Succ => "E-Succ",
+            Rule::PredZero => "E-PredZero",
+            Rule::PredSucc => "E-PredSucc",
+            Rule::Pred => "E-Pred",
+            Rule::IsZeroZero => "E-IsZeroZero",
+            Rule::IsZeroSucc => "E-IsZeroSucc",
+            Rule::IsZero => "E-IsZero",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A derivation of `term -> result`. Every arith rule has at most one
+/// premise, so derivations are chains ending in an axiom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Derivation {
+    pub rule: Rule,
+    pub term: Term,
+    pub result: Term,
+    pub premise: Option<Box<Derivation>>,
+}
+
+impl Derivation {
+    fn axiom(rule: Rule, term: Term, result: Term) -> Self {
+        Derivation { rule, term, result, premise: None }
+    }
+
+    // A congruence rule: `wrap` rebuilds the term around its subterm.
+    fn congruence<F: Fn(Term) -> Term>(rule: Rule, term: Term, premise: Derivation, wrap: F) -> Self {
+        let result = wrap(premise.result.clone());
+        Derivation { rule, term, result, premise: Some(Box::new(premise)) }
+    }
+
+    /// The derivation as indented text, the conclusion first and each
+    /// premise one level further in.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let mut derivation = Some(self);
+        let mut depth = 0;
+        while let Some(d) = derivation {
+            out.push_str(&format!("{}{} -> {}  [{}]\n", "  ".repeat(depth), d.term, d.result, d.rule));
+            derivation = d.premise.as_deref();
+            depth += 1;
+        }
+        out
+    }
+
+    /// The derivation as a `prooftree` for the LaTeX bussproofs package.
+    pub fn to_latex(&self) -> String {
+        let mut chain = vec![];
+        let mut derivation = Some(self);
+        while let Some(d) = derivation {
+            chain.push(d);
+            derivation = d.premise.as_deref();
+        }
+
+        let mut out = String::from("\\begin{prooftree}\n\\AxiomC{}\n");
+        for d in chain.iter().rev() {
+            out.push_str(&format!("\\RightLabel{{\\textsc{{{}}}}}\n", d.rule));
+            out.push_str(&format!("\\UnaryInfC{{${} \\longrightarrow {}$}}\n", latex(&d.term), latex(&d.result)));
+        }
+        out.push_str("\\end{prooftree}\n");
+        out
+    }
+}
+
+// Prints a term as LaTeX math in TAPL's notation.
+fn latex(t: &Term) -> String {
+    let keyword = |k: &str| format!("\\mathsf{{{}}}", k);
+    let atom = |t: &Term| match t {
+        Term::True | Term::False | Term::Zero => latex(t),
+        _ => format!("({})", latex(t)),
+    };
+    let parts = match t {
+        Term::True => vec![keyword("true")],
+        Term::False => vec![keyword("false")],
+        Term::Zero => vec![String::from("0")],
+        Term::Succ(t1) => vec![keyword("succ"), atom(t1)],
+        Term::Pred(t1) => vec![keyword("pred"), atom(t1)],
+        Term::IsZero(t1) => vec![keyword("iszero"), atom(t1)],
+        Term::If(t1, t2, t3) => {
+            let mut parts = vec![keyword("if"), atom(t1), keyword("then"), atom(t2)];
+            if let Some(t3) = t3 {
+                parts.push(keyword("else"));
+                parts.push(atom(t3));
+            }
+            parts
+        }
+    };
+    parts.join("\\ ")
+}
+
+/// Like `eval1`, but returns the derivation justifying the step.
+pub fn derive1(term: &Term) -> Result<Derivation, EvalError> {
+    match term {
+        Term::If(t1, t2, t3) => match **t1 {
+            Term::True => Ok(Derivation::axiom(Rule::IfTrue, term.clone(), (**t2).clone())),
+            Term::False => match t3 {
+                Some(t3) => Ok(Derivation::axiom(Rule::IfFalse, term.clone(), (**t3).clone())),
+                None => Err(EvalError::NoElse((**t1).clone())),
+            },
+            _ => Ok(Derivation::congruence(Rule::If, term.clone(), derive1(t1)?, |t1| {
+                Term::If(Box::new(t1), t2.clone(), t3.clone())
+            })),
+        },
+        Term::Succ(t1) => Ok(Derivation::congruence(Rule::Succ, term.clone(), derive1(t1)?, |t1| {
+            Term::Succ(Box::new(t1))
+        })),
+        Term::Pred(t1) => match **t1 {
+            Term::Zero => Ok(Derivation::axiom(Rule::PredZero, term.clone(), Term::Zero)),
+            Term::Succ(ref nv) if is_numeric_val(nv) => Ok(Derivation::axiom(Rule::PredSucc, term.clone(), (**nv).clone())),
+            _ => Ok(Derivation::congruence(Rule::Pred, term.clone(), derive1(t1)?, |t1| Term::Pred(Box::new(t1)))),
+        },
+        Term::IsZero(t1) => match **t1 {
+            Term::Zero => Ok(Derivation::axiom(Rule::IsZeroZero, term.clone(), Term::True)),
+            Term::Succ(ref nv) if is_numeric_val(nv) => Ok(Derivation::axiom(Rule::IsZeroSucc, term.clone(), Term::False)),
+            _ => Ok(Derivation::congruence(Rule::IsZero, term.clone(), derive1(t1)?, |t1| Term::IsZero(Box::new(t1)))),
+        },
+        _ => Err(EvalError::NoRule(term.clone())),
+    }
+}
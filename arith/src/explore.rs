@@ -0,0 +1,112 @@
+use std::collections::{HashMap, HashSet};
+use crate::arith::{is_numeric_val, Term};
+
+/// Every term `term` reduces to in one step when the congruence rules may
+/// fire in any subterm, including the branches of an `if`, rather than
+/// only in the position `eval1` picks.
+pub fn reducts(term: &Term) -> Vec<Term> {
+    let mut out = vec![];
+    match term {
+        Term::If(t1, t2, t3) => {
+            match (&**t1, t3) {
+                (Term::True, _) => out.push((**t2).clone()),
+                (Term::False, Some(t3)) => out.push((**t3).clone()),
+                _ => {}
+            }
+            out.extend(reducts(t1).into_iter().map(|t1| Term::If(Box::new(t1), t2.clone(), t3.clone())));
+            out.extend(reducts(t2).into_iter().map(|t2| Term::If(t1.clone(), Box::new(t2), t3.clone())));
+            if let Some(t3) = t3 {
+                out.extend(reducts(t3).into_iter().map(|t3| Term::If(t1.clone(), t2.clone(), Some(Box::new(t3)))));
+            }
+        }
+        Term::Succ(t1) => out.extend(reducts(t1).into_iter().map(|t1| Term::Succ(Box::new(t1)))),
+        Term::Pred(t1) => {
+            match &**t1 {
+                Term::Zero => out.push(Term::Zero),
+                Term::Succ(nv) if is_numeric_val(nv) => out.push((**nv).clone()),
+                _ => {}
+            }
+            out.extend(reducts(t1).into_iter().map(|t1| Term::Pred(Box::new(t1))));
+        }
+        Term::IsZero(t1) => {
+            match &**t1 {
+                Term::Zero => out.push(Term::True),
+                Term::Succ(nv) if is_numeric_val(nv) => out.push(Term::False),
+                _ => {}
+            }
+            out.extend(reducts(t1).into_iter().map(|t1| Term::IsZero(Box::new(t1))));
+        }
+        Term::True | Term::False | Term::Zero => {}
+    }
+    out
+}
+
+/// The terms reachable from a start term, which is `nodes[0]`, with an
+/// edge for every single step between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Graph {
+    pub nodes: Vec<Term>,
+    pub edges: Vec<(usize, usize)>,
+    // Whether every node was expanded before `bound` was reached.
+    pub complete: bool,
+}
+
+impl Graph {
+    /// Explores breadth first from `term` with the default rule set,
+    /// `reducts`, stopping after `bound` terms.
+    pub fn new(term: Term, bound: usize) -> Self {
+        Graph::with_rules(term, bound, reducts)
+    }
+
+    /// Like `new`, but with `step` giving the one-step reducts, so
+    /// variants of the rule set can be explored.
+    pub fn with_rules<F: Fn(&Term) -> Vec<Term>>(term: Term, bound: usize, step: F) -> Self {
+        let mut index = HashMap::new();
+        index.insert(term.clone(), 0);
+        let mut graph = Graph { nodes: vec![term], edges: vec![], complete: true };
+        let mut next = 0;
+        while next < graph.nodes.len() {
+            if next == bound {
+                graph.complete = false;
+                break;
+            }
+            // Distinct rules can lead to the same reduct; it gets one edge.
+            let mut targets = HashSet::new();
+            for reduct in step(&graph.nodes[next]) {
+                let to = match index.get(&reduct) {
+                    Some(&to) => to,
+                    None => {
+                        index.insert(reduct.clone(), graph.nodes.len());
+                        graph.nodes.push(reduct);
+                        graph.nodes.len() - 1
+                    }
+                };
+                if targets.insert(to) {
+                    graph.edges.push((next, to));
+                }
+            }
+            next += 1;
+        }
+        graph
+    }
+
+    /// The reachable terms with no step out of them. Only meaningful when
+    /// the graph is complete.
+    pub fn normal_forms(&self) -> Vec<&Term> {
+        let sources: HashSet<usize> = self.edges.iter().map(|&(from, _)| from).collect();
+        (0..self.nodes.len())
+            .filter(|i| !sources.contains(i))
+            .map(|i| &self.nodes[i])
+            .collect()
+    }
+
+    /// Whether every path from the start term ends in the same normal
+    /// form, or `None` if the bound stopped the exploration first.
+    pub fn unique_normal_form(&self) -> Option<bool> {
+        if self.complete {
+            Some(self.normal_forms().len() == 1)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use tyarith::rng::Rng;
+use tyarith::tyarith::Type;
+use crate::arith::Term;
+
+/// A seeded source of random terms, drawn from the sets `S_i` of TAPL
+/// definition 3.2.3: `S_1` holds the constants and `S_{i+1}` every term
+/// built from terms in `S_i`, here including an `if` without else.
+pub struct Generator {
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator {
+            rng: Rng::new(seed)
+        }
+    }
+
+    /// A random term of `S_depth`. Every production is equally likely, so
+    /// most terms are well-formed but ill-typed, like `succ true`.
+    pub fn term(&mut self, depth: usize) -> Term {
+        assert!(depth > 0, "S_0 is empty");
+        let choice = if depth == 1 { self.rng.below(3) } else { self.rng.below(8) };
+        match choice {
+            0 => Term::True,
+            1 => Term::False,
+            2 => Term::Zero,
+            3 => Term::Succ(Box::new(self.term(depth - 1))),
+            4 => Term::Pred(Box::new(self.term(depth - 1))),
+            5 => Term::IsZero(Box::new(self.term(depth - 1))),
+            6 => {
+                let term1 = self.term(depth - 1);
+                let term2 = self.term(depth - 1);
+                Term::If(Box::new(term1), Box::new(term2), None)
+            }
+            _ => {
+                let term1 = self.term(depth - 1);
+                let term2 = self.term(depth - 1);
+                let term3 = self.term(depth - 1);
+                Term::If(Box::new(term1), Box::new(term2), Some(Box::new(term3)))
+            }
+        }
+    }
+
+    /// A random term of type `ty` and depth at most `depth`, which always
+    /// evaluates to a value. `succ` is drawn twice as often as `pred`.
+    pub fn typed(&mut self, ty: Type, depth: usize) -> Term {
+        let (leaves, rules) = match ty {
+            Type::Nat => (1, 5),
+            Type::Bool => (2, 4),
+        };
+        let choice = if depth <= 1 { self.rng.below(leaves) } else { self.rng.below(rules) };
+        match (ty, choice) {
+            (Type::Nat, 0) => Term::Zero,
+            (Type::Nat, 1) | (Type::Nat, 2) => Term::Succ(Box::new(self.typed(Type::Nat, depth - 1))),
+            (Type::Nat, 3) => Term::Pred(Box::new(self.typed(Type::Nat, depth - 1))),
+            (Type::Bool, 0) => Term::True,
+            (Type::Bool, 1) => Term::False,
+            (Type::Bool, 2) => Term::IsZero(Box::new(self.typed(Type::Nat, depth - 1))),
+            _ => {
+                let term1 = self.typed(Type::Bool, depth - 1);
+                let term2 = self.typed(ty, depth - 1);
+                let term3 = self.typed(ty, depth - 1);
+                Term::If(Box::new(term1), Box::new(term2), Some(Box::new(term3)))
+            }
+        }
+    }
+}
+
+/// Every term of `S_depth`. The sets grow quickly: `S_3` already has
+/// 113043 terms.
+pub fn terms(depth: usize) -> Vec<Term> {
+    if depth == 0 {
+        return vec![];
+    }
+    let smaller = terms(depth - 1);
+    let mut result = vec![Term::True, Term::False, Term::Zero];
+    for t in smaller.iter() {
+        result.push(Term::Succ(Box::new(t.clone())));
+        result.push(Term::Pred(Box::new(t.clone())));
+        result.push(Term::IsZero(Box::new(t.clone())));
+    }
+    for t1 in smaller.iter() {
+        for t2 in smaller.iter() {
+            result.push(Term::If(Box::new(t1.clone()), Box::new(t2.clone()), None));
+            for t3 in smaller.iter() {
+                result.push(Term::If(Box::new(t1.clone()), Box::new(t2.clone()), Some(Box::new(t3.clone()))));
+            }
+        }
+    }
+    result
+}
@@ -1,6 +1,16 @@
 #[cfg(test)]
 mod tests {
-    use crate::arith::{eval, Term, big_eval};
+    use crate::arith::{eval, eval1, eval_iterative, steps, Step, Term, EvalError, big_eval, classify, Classification, StuckReason};
+    use crate::bridge::{from_tyarith, incompleteness_witness, incompleteness_witnesses, type_of};
+    use crate::derivation::{derive1, Rule};
+    use crate::explore::{reducts, Graph};
+    use crate::generator::{terms, Generator};
+    use crate::metrics::{consts, consts_lemma_holds, depth, size};
+    use crate::parser::{Lexer, Parser};
+    use crate::tagless::{interpret, Eval, Print, Size, TypeCheck};
+    use crate::vm::{compile, disassemble, run, Value, VmError};
+    use crate::wrong;
+    use tyarith::tyarith::Type;
     use crate::arith::Term::{Zero, True, False, IsZero, Pred, Succ, If};
 
     #[test]
@@ -12,25 +22,368 @@ mod tests {
         assert_eq!(eval(Pred(Box::new(Zero))), Zero);
         assert_eq!(eval(Succ(Box::new(Zero))), Succ(Box::new(Zero)));
         assert_eq!(eval(Pred(Box::new(Succ(Box::new(Zero))))), Zero);
+        assert_eq!(eval(Succ(Box::new(If(Box::new(True), Box::new(Zero), None)))), Succ(Box::new(Zero)));
         assert_eq!(eval(If(Box::new(True), Box::new(Pred(Box::new(Zero))), Option::from(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))))), Zero);
         assert_eq!(eval(If(Box::new(False), Box::new(Pred(Box::new(Zero))), Option::from(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))))), Succ(Box::new(Zero)));
 
     }
 
+    // E-Succ steps inside `succ` whatever the subterm is. It used to step
+    // only under `succ (pred t)`, and panicked when that `pred t` was stuck.
     #[test]
-    fn big_eval_test() {
-        assert_eq!(big_eval(Zero), Zero);
-        assert_eq!(big_eval(True), True);
-        assert_eq!(big_eval(False), False);
-        assert_eq!(big_eval(IsZero(Box::new(Zero))), True);
-        assert_eq!(big_eval(Pred(Box::new(Zero))), Zero);
-        assert_eq!(big_eval(Succ(Box::new(Zero))), Succ(Box::new(Zero)));
-        assert_eq!(big_eval(Pred(Box::new(Succ(Box::new(Zero))))), Zero);
-        assert_eq!(big_eval(If(Box::new(True), Box::new(Pred(Box::new(Zero))), Option::from(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))))), Zero);
-        assert_eq!(big_eval(If(Box::new(False), Box::new(Pred(Box::new(Zero))), Option::from(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))))), Succ(Box::new(Zero)));
+    fn eval1_succ_test() {
+        assert_eq!(eval1(parse("succ (succ (pred 0))").remove(0)), Ok(parse("succ (succ 0)").remove(0)));
+        assert_eq!(eval1(parse("succ (if true then 0 else 1)").remove(0)), Ok(parse("succ 0").remove(0)));
+        assert_eq!(eval1(parse("succ (iszero 0)").remove(0)), Ok(parse("succ true").remove(0)));
+        assert_eq!(eval1(parse("succ (pred true)").remove(0)), Err(EvalError::NoRule(True)));
+        assert_eq!(eval(parse("succ (succ (pred (succ 0)))").remove(0)), parse("2").remove(0));
+    }
+
+    #[test]
+    fn classify_test() {
+        assert_eq!(classify(Pred(Box::new(Succ(Box::new(Zero))))), Classification::Numeric(Zero));
+        assert_eq!(classify(IsZero(Box::new(Zero))), Classification::Boolean(True));
+
+        // succ (pred (iszero false))
+        let stuck = IsZero(Box::new(False));
+        assert_eq!(classify(Succ(Box::new(Pred(Box::new(stuck.clone()))))), Classification::Stuck {
+            normal_form: Succ(Box::new(Pred(Box::new(stuck.clone())))),
+            subterm: stuck,
+            reason: StuckReason::NotNumeric,
+        });
+        let stuck = If(Box::new(Zero), Box::new(True), Some(Box::new(False)));
+        assert_eq!(classify(IsZero(Box::new(stuck.clone()))), Classification::Stuck {
+            normal_form: IsZero(Box::new(stuck.clone())),
+            subterm: stuck,
+            reason: StuckReason::NotBoolean,
+        });
+        let stuck = If(Box::new(False), Box::new(Zero), None);
+        match classify(If(Box::new(IsZero(Box::new(Succ(Box::new(Zero))))), Box::new(Zero), None)) {
+            Classification::Stuck { subterm, reason, .. } => assert_eq!((subterm, reason), (stuck, StuckReason::NoElse)),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    // Checks a property on every term of `S_3` and on 2000 random terms of
+    // depth 6, deeper than `S_3` reaches.
+    fn for_sample_terms<F: FnMut(Term)>(seed: u64, mut check: F) {
+        terms(3).into_iter().for_each(&mut check);
+        let mut gen = Generator::new(seed);
+        for _ in 0..2000 {
+            check(gen.term(6));
+        }
+    }
+
+    // Exercise 3.5.17: `t ->* v` exactly when `t ⇓ v`, and stuck terms have
+    // no big-step derivation.
+    fn assert_agree(t: Term) {
+        match (classify(t.clone()), big_eval(t.clone())) {
+            (Classification::Numeric(v), Ok(w)) | (Classification::Boolean(v), Ok(w)) => assert_eq!(v, w, "{:?}", t),
+            (Classification::Stuck { .. }, Err(_)) => {}
+            (small, big) => panic!("{:?}: eval gives {:?}, big_eval gives {:?}", t, small, big.map_err(|e| format!("{:?}", e))),
+        }
+    }
+
+    #[test]
+    fn eval_iterative_test() {
+        for_sample_terms(15, |t| assert_eq!(eval_iterative(t.clone()), eval(t)));
+        assert_eq!(eval_iterative(If(Box::new(False), Box::new(Zero), None)), If(Box::new(False), Box::new(Zero), None));
+
+        // Far deeper than the recursive evaluators can go.
+        let deep = |wrap: fn(Box<Term>) -> Term, t: Term| (0..100_000).fold(t, |t, _| wrap(Box::new(t)));
+        assert_eq!(eval_iterative(deep(Pred, Zero)), Zero);
+        assert_eq!(eval_iterative(IsZero(Box::new(deep(Succ, deep(Pred, Zero))))), False);
+        let t = If(Box::new(IsZero(Box::new(deep(Pred, Zero)))), Box::new(Zero), Some(Box::new(deep(Succ, Zero))));
+        assert_eq!(eval_iterative(t), Zero);
+    }
+
+    #[test]
+    fn vm_test() {
+        let code = compile(&parse("if iszero 0 then succ 0 else pred 0").remove(0));
+        assert_eq!(disassemble(&code), concat!(
+            "0000  push_zero\n",
+            "0001  iszero\n",
+            "0002  jump_if_false 0006\n",
+            "0003  push_zero\n",
+            "0004  succ\n",
+            "0005  jump 0008\n",
+            "0006  push_zero\n",
+            "0007  pred\n",
+        ));
+        assert_eq!(run(&code), Ok(Value::Nat(1)));
+        assert_eq!(run(&compile(&parse("if false then 0").remove(0))), Err(VmError::NoElse { pc: 4 }));
+        assert_eq!(run(&compile(&parse("succ true").remove(0))), Err(VmError::ExpectedNat { pc: 1, found: Value::Bool(true) }));
+
+        let agree = |t: Term| match (run(&compile(&t)), classify(t.clone())) {
+            (Ok(v), Classification::Numeric(w)) | (Ok(v), Classification::Boolean(w)) => assert_eq!(v.to_term(), w, "{}", t),
+            (Err(_), Classification::Stuck { .. }) => {}
+            (vm, eval) => panic!("{}: the vm gives {:?}, eval gives {:?}", t, vm, eval),
+        };
+        for_sample_terms(16, agree);
+    }
+
+    #[test]
+    fn wrong_test() {
+        let wrong_eval = |input: &str| wrong::eval(wrong::Term::from(&parse(input).remove(0)));
+        assert_eq!(wrong_eval("pred (succ 1)"), wrong::Term::from(&parse("1").remove(0)));
+        assert_eq!(wrong_eval("if iszero 0 then true else 0"), wrong::Term::True);
+        assert_eq!(wrong_eval("succ (if 0 then 0 else 0)"), wrong::Term::Wrong);
+        assert_eq!(wrong_eval("iszero (pred true)"), wrong::Term::Wrong);
+        assert_eq!(wrong_eval("if false then 0"), wrong::Term::Wrong);
+        assert_eq!(wrong::eval1(&wrong::Term::from(&parse("succ false").remove(0))), Some(wrong::Term::Wrong));
+        assert_eq!(wrong::Term::If(Box::new(wrong::Term::Wrong), Box::new(wrong::Term::Zero), None).to_string(), "if wrong then 0");
+
+        for_sample_terms(17, |t| assert!(wrong::agrees(&t), "{}", t));
+    }
+
+    #[test]
+    fn explore_test() {
+        let t = parse("if iszero 0 then pred 1 else succ (pred 0)").remove(0);
+        assert_eq!(reducts(&t), parse("if true then pred 1 else succ (pred 0); if iszero 0 then 0 else succ (pred 0); if iszero 0 then pred 1 else 1"));
+        let graph = Graph::new(t, 100);
+        assert_eq!(graph.nodes.len(), 10);
+        assert_eq!(graph.normal_forms(), vec![&Zero]);
+        assert_eq!(graph.unique_normal_form(), Some(true));
+        assert_eq!(Graph::new(parse("succ (pred (pred 2))").remove(0), 2).unique_normal_form(), None);
+
+        // With an extra rule that lets `if` pick either branch, reduction is
+        // no longer confluent.
+        let either = |t: &Term| {
+            let mut out = reducts(t);
+            if let If(_, t2, Some(t3)) = t {
+                out.push((**t2).clone());
+                out.push((**t3).clone());
+            }
+            out
+        };
+        let graph = Graph::with_rules(parse("if true then 0 else false").remove(0), 100, either);
+        assert_eq!(graph.unique_normal_form(), Some(false));
+        // E-IfTrue and the extra rule both step to `0`, over one edge.
+        assert_eq!(graph.edges, vec![(0, 1), (0, 2)]);
+
+        for t in terms(3) {
+            if let Ok(next) = eval1(t.clone()) {
+                assert!(reducts(&t).contains(&next), "{}", t);
+            }
+            let graph = Graph::new(t.clone(), 10_000);
+            assert_eq!(graph.unique_normal_form(), Some(true), "{}", t);
+            // Stuck terms can still step inside their branches, so only
+            // values are the normal form `eval` reaches.
+            if !matches!(classify(t.clone()), Classification::Stuck { .. }) {
+                assert_eq!(graph.normal_forms(), vec![&eval(t)]);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_test() {
+        let t = parse("pred (succ (if true then 0 else 1))").remove(0);
+        assert_eq!(steps(t).collect::<Vec<Step>>(), vec![
+            Step::Term(parse("pred (succ (if true then 0 else 1))").remove(0)),
+            Step::Term(parse("pred 1").remove(0)),
+            Step::Done(Classification::Numeric(Zero)),
+        ]);
+        let t = parse("succ (iszero 0)").remove(0);
+        assert_eq!(steps(t).last(), Some(Step::Done(Classification::Stuck {
+            normal_form: Succ(Box::new(True)),
+            subterm: Succ(Box::new(True)),
+            reason: StuckReason::NotNumeric,
+        })));
+
+        for t in terms(3) {
+            let items: Vec<Step> = steps(t.clone()).collect();
+            assert_eq!(items.last(), Some(&Step::Done(classify(t.clone()))));
+            let mut expected = t;
+            for item in &items[..items.len() - 1] {
+                assert_eq!(item, &Step::Term(expected.clone()));
+                expected = eval1(expected).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn tagless_test() {
+        let input = "if iszero (pred 1) then succ (if true then 2) else pred (succ false); iszero true";
+        let mut lex = Lexer::new(input.chars());
+        lex.lex_input();
+        let tokens = lex.result;
+        let values = Parser::with_interpretation(tokens.clone().into_iter(), Eval).parse_commands();
+        let printed: Vec<String> = Parser::with_interpretation(tokens.clone().into_iter(), Print).parse_commands()
+            .iter().map(|p| p.to_string()).collect();
+        let types = Parser::with_interpretation(tokens.clone().into_iter(), TypeCheck).parse_commands();
+        let sizes = Parser::with_interpretation(tokens.into_iter(), Size).parse_commands();
+        assert_eq!(values, vec![Some(Value::Nat(3)), None]);
+        assert_eq!(printed, vec!["if iszero (pred 1) then succ (if true then 2) else pred (succ false)", "iszero true"]);
+        assert_eq!(types, vec![Err(tyarith::tyarith::TyError::TypingError), Err(tyarith::tyarith::TyError::TypingError)]);
+        assert_eq!(sizes, vec![14, 2]);
 
+        for_sample_terms(20, |t| {
+            let expected = match classify(t.clone()) {
+                Classification::Numeric(v) | Classification::Boolean(v) => Some(v),
+                Classification::Stuck { .. } => None,
+            };
+            assert_eq!(interpret(&t, &Eval).map(Value::to_term), expected, "{}", t);
+            assert_eq!(interpret(&t, &Print).to_string(), t.to_string());
+            assert_eq!(interpret(&t, &Size), size(&t));
+            let printed = t.to_string();
+            let mut lex = tyarith::parser::Lexer::new(printed.chars());
+            lex.lex_input();
+            let typed = tyarith::parser::Parser::new(lex.result.into_iter()).parse_commands().remove(0);
+            assert_eq!(interpret(&t, &TypeCheck), tyarith::tyarith::type_check(typed), "{}", t);
+        });
+    }
+
+    #[test]
+    fn bridge_test() {
+        use tyarith::derivation::Position;
+
+        assert_eq!(type_of(&parse("if iszero 0 then succ 0 else 0").remove(0)), Ok(Type::Nat));
+        let error = type_of(&parse("pred (iszero 0)").remove(0)).unwrap_err();
+        assert_eq!((error.position, error.subterm.to_string()), (Position::PredArgument, String::from("iszero 0")));
+
+        let t = parse("if true then 0 else false").remove(0);
+        let witness = incompleteness_witness(&t).unwrap();
+        assert_eq!(witness.value, Zero);
+        assert_eq!(witness.error.to_string(), "else branch of if has type Bool, expected Nat");
+        assert_eq!(incompleteness_witness(&parse("succ true").remove(0)), None);
+        assert_eq!(incompleteness_witness(&parse("succ 0").remove(0)), None);
+
+        let witnesses = incompleteness_witnesses(2);
+        assert!(witnesses.iter().any(|w| w.term == t));
+        assert!(witnesses.iter().all(|w| type_of(&w.term).is_err() && !matches!(classify(w.term.clone()), Classification::Stuck { .. })));
+
+        for_sample_terms(25, |t| {
+            let converted = tyarith::tyarith::Term::from(&t);
+            assert_eq!(from_tyarith(&converted), Some(t.clone()));
+            // Typing is sound except that an `if` without else gets stuck
+            // when its condition is false.
+            match (type_of(&t), classify(t.clone())) {
+                (Ok(Type::Nat), Classification::Numeric(_)) | (Ok(Type::Bool), Classification::Boolean(_)) | (Err(_), _) => {}
+                (Ok(_), Classification::Stuck { reason: StuckReason::NoElse, .. }) => {}
+                (ty, result) => panic!("{} : {:?} evaluates to {:?}", t, ty, result),
+            }
+        });
+        assert_eq!(from_tyarith(&tyarith::tyarith::Term::Num(2)), Some(Succ(Box::new(Succ(Box::new(Zero))))));
+        assert_eq!(from_tyarith(&tyarith::tyarith::Term::Num(u64::MAX)), None);
+        assert_eq!(from_tyarith(&tyarith::tyarith::Term::nat(1000)).map(|t| size(&t)), Some(1001));
+    }
+
+    #[test]
+    fn generator_test() {
+        assert_eq!(terms(1).len(), 3);
+        assert_eq!(terms(2).len(), 48);
+        assert_eq!(Generator::new(7).term(5), Generator::new(7).term(5));
+        assert!(terms(3).contains(&Generator::new(7).term(3)));
+
+        let mut gen = Generator::new(8);
+        for ty in [Type::Nat, Type::Bool].iter().cycle().take(200) {
+            let t = gen.typed(*ty, 6);
+            assert!(depth(&t) <= 6);
+            assert_eq!(type_of(&t), Ok(*ty), "{}", t);
+            assert!(!matches!(classify(t), Classification::Stuck { .. }));
+        }
+    }
+
+    #[test]
+    fn eval_big_eval_agree_test() {
+        for_sample_terms(3517, assert_agree);
+    }
+
+    #[test]
+    fn derive1_test() {
+        // if iszero (pred (succ zero)) then zero else succ zero
+        let t = If(Box::new(IsZero(Box::new(Pred(Box::new(Succ(Box::new(Zero))))))), Box::new(Zero), Some(Box::new(Succ(Box::new(Zero)))));
+        let d = derive1(&t).ok().unwrap();
+        assert_eq!(d.rule, Rule::If);
+        assert_eq!(d.premise.as_ref().unwrap().rule, Rule::IsZero);
+        assert_eq!(d.premise.as_ref().unwrap().premise.as_ref().unwrap().rule, Rule::PredSucc);
+        assert_eq!(d.to_text(), concat!(
+            "if iszero (pred 1) then 0 else 1 -> if iszero 0 then 0 else 1  [E-If]\n",
+            "  iszero (pred 1) -> iszero 0  [E-IsZero]\n",
+            "    pred 1 -> 0  [E-PredSucc]\n",
+        ));
+        assert_eq!(derive1(&IsZero(Box::new(Zero))).ok().unwrap().to_latex(), concat!(
+            "\\begin{prooftree}\n",
+            "\\AxiomC{}\n",
+            "\\RightLabel{\\textsc{E-IsZeroZero}}\n",
+            "\\UnaryInfC{$\\mathsf{iszero}\\ 0 \\longrightarrow \\mathsf{true}$}\n",
+            "\\end{prooftree}\n",
+        ));
+
+        let mut t = t;
+        while let Ok(d) = derive1(&t) {
+            assert_eq!(Some(d.result.clone()), eval1(t).ok());
+            t = d.result;
+        }
+        assert_eq!(t, Zero);
+        assert_eq!(derive1(&Succ(Box::new(True))).ok(), None);
+    }
+
+    #[test]
+    fn metrics_test() {
+        // if iszero zero then succ zero else zero
+        let t = If(Box::new(IsZero(Box::new(Zero))), Box::new(Succ(Box::new(Zero))), Some(Box::new(Zero)));
+        assert_eq!((size(&t), depth(&t)), (6, 3));
+        assert_eq!(consts(&t).into_iter().collect::<Vec<Term>>(), vec![Zero]);
+        assert_eq!(consts(&If(Box::new(True), Box::new(False), None)).len(), 2);
+
+        let s2 = terms(2);
+        for_sample_terms(333, |t| {
+            assert!(consts_lemma_holds(&t));
+            assert!(depth(&t) <= 6 && depth(&t) <= size(&t));
+            assert_eq!(depth(&t) <= 2, s2.contains(&t));
+        });
+    }
+
+    fn parse(input: &str) -> Vec<Term> {
+        let mut lex = Lexer::new(input.chars());
+        lex.lex_input();
+        Parser::new(lex.result.into_iter()).parse_commands()
+    }
+
+    #[test]
+    fn display_test() {
+        let t = If(Box::new(True), Box::new(If(Box::new(False), Box::new(Zero), None)), Some(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))));
+        assert_eq!(t.to_string(), "if true then (if false then 0) else succ (pred 0)");
+        assert_eq!(parse(&t.to_string()), vec![t]);
+        assert_eq!(parse("3; succ 2; pred 10"), vec![
+            Succ(Box::new(Succ(Box::new(Succ(Box::new(Zero)))))),
+            Succ(Box::new(Succ(Box::new(Succ(Box::new(Zero)))))),
+            Pred(Box::new(eval(parse("10").remove(0)))),
+        ]);
+        assert_eq!(eval(parse("pred 10").remove(0)).to_string(), "9");
+        assert_eq!(size(&parse("1000").remove(0)), 1001);
+        let err = std::panic::catch_unwind(|| parse("18446744073709551615")).unwrap_err();
+        assert_eq!(err.downcast_ref::<String>().map(String::as_str), Some("Number too large `18446744073709551615`, literals go up to 1000~~"));
+
+        for_sample_terms(13, |t| assert_eq!(parse(&t.to_string()), vec![t.clone()]));
+    }
+
+    #[test]
+    fn big_eval_test() {
+        assert_eq!(big_eval(Zero), Ok(Zero));
+        assert_eq!(big_eval(True), Ok(True));
+        assert_eq!(big_eval(False), Ok(False));
+        assert_eq!(big_eval(IsZero(Box::new(Zero))), Ok(True));
+        assert_eq!(big_eval(Pred(Box::new(Zero))), Ok(Zero));
+        assert_eq!(big_eval(Succ(Box::new(Zero))), Ok(Succ(Box::new(Zero))));
+        assert_eq!(big_eval(Pred(Box::new(Succ(Box::new(Zero))))), Ok(Zero));
+        assert_eq!(big_eval(If(Box::new(True), Box::new(Pred(Box::new(Zero))), Option::from(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))))), Ok(Zero));
+        assert_eq!(big_eval(If(Box::new(False), Box::new(Pred(Box::new(Zero))), Option::from(Box::new(Succ(Box::new(Pred(Box::new(Zero)))))))), Ok(Succ(Box::new(Zero))));
+        assert_eq!(big_eval(Succ(Box::new(Pred(Box::new(Succ(Box::new(Zero))))))), Ok(Succ(Box::new(Zero))));
+        let t = If(Box::new(IsZero(Box::new(Succ(Box::new(Zero))))), Box::new(Zero), None);
+        assert_eq!(big_eval(t), Err(EvalError::NoElse(If(Box::new(False), Box::new(Zero), None))));
+        assert!(big_eval(Succ(Box::new(IsZero(Box::new(Zero))))).is_err());
     }
 }
 
 pub mod parser;
 pub mod arith;
+pub mod bridge;
+pub mod derivation;
+pub mod explore;
+pub mod generator;
+pub mod metrics;
+pub mod tagless;
+pub mod vm;
+pub mod wrong;
@@ -0,0 +1,29 @@
+use std::collections::HashSet;
+use tyarith::metrics;
+use tyarith::tyarith as typed;
+use crate::arith::Term;
+use crate::bridge::from_tyarith;
+
+// Every arith term is a tyarith term, so these measure the tyarith term it
+// converts to.
+
+/// The constants appearing in `t`, TAPL definition 3.3.1.
+pub fn consts(t: &Term) -> HashSet<Term> {
+    metrics::consts(&typed::Term::from(t)).iter().filter_map(from_tyarith).collect()
+}
+
+/// The number of nodes in the syntax tree of `t`, TAPL definition 3.3.2.
+pub fn size(t: &Term) -> usize {
+    metrics::size(&typed::Term::from(t))
+}
+
+/// The number of nodes on the longest root-to-leaf path of `t`, TAPL
+/// definition 3.3.2. It is the smallest `i` with `t` in `S_i`.
+pub fn depth(t: &Term) -> usize {
+    metrics::depth(&typed::Term::from(t))
+}
+
+/// Lemma 3.3.3: a term has no more distinct constants than nodes.
+pub fn consts_lemma_holds(t: &Term) -> bool {
+    metrics::consts_lemma_holds(&typed::Term::from(t))
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use tyarith::tyarith::MAX_CHAIN_LITERAL;
 use crate::arith::Term;
+use crate::tagless::{Arith, Syntax};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
@@ -14,8 +16,11 @@ pub enum Token {
     Then,
     Else,
 
+    Num(u64),
+
     LParen,
     RParen,
+    Semi,
 }
 
 pub fn get_keywords() -> HashMap<String, Token> {
@@ -84,17 +89,32 @@ where
                     self.result.push(out);
                 }
                 '(' => {
-                    //self.result.push(Token::LParen);
+                    self.result.push(Token::LParen);
                     self.next_char();
                 }
                 ')' => {
-                    //self.result.push(Token::RParen);
+                    self.result.push(Token::RParen);
                     self.next_char();
                 }
-                ' ' => {
+                ';' => {
+                    self.result.push(Token::Semi);
+                    self.next_char();
+                }
+                '0'..='9' => {
+                    let mut digits = String::new();
+                    while let Some(c) = self.chr0.filter(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                        self.next_char();
+                    }
+                    match digits.parse::<u64>() {
+                        Ok(n) => self.result.push(Token::Num(n)),
+                        Err(_) => panic!("Number too large `{}`~~", digits),
+                    }
+                }
+                ' ' | '\t' | '\n' | '\r' => {
                     self.next_char();
                 },
-                _ => {}
+                c => panic!("Unknown character `{}`~~", c),
             }
 
         }
@@ -109,9 +129,10 @@ where
 }
 
 #[derive(Clone, PartialEq)]
-pub struct Parser<T: Iterator<Item = Token> + Debug + Clone> {
+pub struct Parser<T: Iterator<Item = Token> + Debug + Clone, A: Arith = Syntax> {
     token: T,
     tok0: Option<Token>,
+    alg: A,
     pub result: Option<Term>,
 }
 
@@ -119,51 +140,87 @@ impl<T> Parser<T>
     where
         T: Iterator<Item = Token> + Debug + Clone{
     pub fn new(input: T) -> Self {
+        Parser::with_interpretation(input, Syntax)
+    }
+}
+
+impl<T, A> Parser<T, A>
+    where
+        T: Iterator<Item = Token> + Debug + Clone,
+        A: Arith {
+    /// A parser that builds each term through `alg` instead of as a
+    /// `Term`.
+    pub fn with_interpretation(input: T, alg: A) -> Self {
 
         let mut parser = Parser {
             token: input,
             tok0: None,
+            alg,
             result: None
         };
         parser.next_token();
         parser
     }
 
-    pub fn expr(&mut self) -> Option<Term> {
+    pub fn expr(&mut self) -> Option<A::Repr> {
         match self.tok0 {
             Some(Token::False) => {
                 self.next_token();
-                Some(Term::False)
+                Some(self.alg.fls())
             },
             Some(Token::True) => {
                 self.next_token();
-                Some(Term::True)
+                Some(self.alg.tru())
             },
             Some(Token::Zero) => {
                 self.next_token();
-                Some(Term::Zero)
+                Some(self.alg.zero())
             },
+            Some(Token::Num(n)) => {
+                // Literals are spelled out as `succ` chains, so a large one
+                // would not fit in memory.
+                if n > MAX_CHAIN_LITERAL {
+                    panic!("Number too large `{}`, literals go up to {}~~", n, MAX_CHAIN_LITERAL);
+                }
+                self.next_token();
+                let mut term = self.alg.zero();
+                for _ in 0..n {
+                    term = self.alg.succ(term);
+                }
+                Some(term)
+            }
             Some(Token::Succ) => {
                 self.next_token();
                 let tmp = self.expr().unwrap();
-                Some(Term::Succ(Box::from(tmp)))
+                Some(self.alg.succ(tmp))
             }
             Some(Token::Pred) => {
                 self.next_token();
-                Some(Term::Pred(Box::from(self.expr().unwrap())))
+                let tmp = self.expr().unwrap();
+                Some(self.alg.pred(tmp))
             }
             Some(Token::If) => {
                 self.parse_if_then_expr()
             }
             Some(Token::IsZero) => {
                 self.next_token();
-                Some(Term::IsZero(Box::new(self.expr().unwrap())))
+                let tmp = self.expr().unwrap();
+                Some(self.alg.iszero(tmp))
+            }
+            Some(Token::LParen) => {
+                self.next_token();
+                let term = self.expr();
+                if self.tok0 != Some(Token::RParen) {
+                    panic!("Expected `)`~~");
+                }
+                self.next_token();
+                term
             }
             _ => panic!("Err~~~"),
         }
     }
 
-    pub fn parse_if_then_expr(&mut self) -> Option<Term> {
+    pub fn parse_if_then_expr(&mut self) -> Option<A::Repr> {
         self.next_token(); //If
         let cond = self.expr();
         self.next_token(); //then
@@ -172,9 +229,36 @@ impl<T> Parser<T>
         if self.tok0 == Some(Token::Else) {
             self.next_token(); //else
             let else_expr = self.expr();
-            return Some(Term::If(Box::new(cond.unwrap()), Box::new(then_expr.unwrap()), Option::from(Box::new(else_expr.unwrap()))));
+            return Some(self.alg.if_(cond.unwrap(), then_expr.unwrap(), Some(else_expr.unwrap())));
+        }
+        return Some(self.alg.if_(cond.unwrap(), then_expr.unwrap(), None));
+    }
+
+    pub fn parse(&mut self) -> Option<A::Repr> {
+        self.expr()
+    }
+
+    pub fn parse_commands(&mut self) -> Vec<A::Repr> {
+        let mut result = vec![];
+        loop {
+            match self.tok0 {
+                None => break,
+                Some(Token::Semi) => {
+                    self.next_token();
+                }
+                _ => {
+                    match self.parse() {
+                        Some(term) => result.push(term),
+                        None => panic!("Err in command~~"),
+                    }
+                    match self.tok0 {
+                        None | Some(Token::Semi) => {}
+                        _ => panic!("Expected `;`~~"),
+                    }
+                }
+            }
         }
-        return Some(Term::If(Box::new(cond.unwrap()), Box::new(then_expr.unwrap()), None));
+        result
     }
 
     pub fn next_token(&mut self) -> Option<Token> {
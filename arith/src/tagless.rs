@@ -0,0 +1,277 @@
+use std::fmt;
+use tyarith::tyarith::{TyError, Type};
+use crate::arith::Term;
+use crate::vm::Value;
+
+/// One method per arith construct, in tagless-final style: an
+/// implementation is an interpretation of arith terms as `Repr`, and the
+/// parser can build any of them directly from concrete syntax.
+pub trait Arith {
+    type Repr;
+
+    fn zero(&self) -> Self::Repr;
+    fn succ(&self, t: Self::Repr) -> Self::Repr;
+    fn pred(&self, t: Self::Repr) -> Self::Repr;
+    fn iszero(&self, t: Self::Repr) -> Self::Repr;
+    fn tru(&self) -> Self::Repr;
+    fn fls(&self) -> Self::Repr;
+    fn if_(&self, t1: Self::Repr, t2: Self::Repr, t3: Option<Self::Repr>) -> Self::Repr;
+}
+
+/// Interprets an existing term.
+pub fn interpret<A: Arith>(t: &Term, alg: &A) -> A::Repr {
+    match t {
+        Term::True => alg.tru(),
+        Term::False => alg.fls(),
+        Term::Zero => alg.zero(),
+        Term::Succ(t1) => alg.succ(interpret(t1, alg)),
+        Term::Pred(t1) => alg.pred(interpret(t1, alg)),
+        Term::IsZero(t1) => alg.iszero(interpret(t1, alg)),
+        Term::If(t1, t2, t3) => alg.if_(interpret(t1, alg), interpret(t2, alg), t3.as_ref().map(|t3| interpret(t3, alg))),
+    }
+}
+
+/// Builds the syntax tree, `arith::arith::Term`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Syntax;
+
+impl Arith for Syntax {
+    type Repr = Term;
+
+    fn zero(&self) -> Term {
+        Term::Zero
+    }
+
+    fn succ(&self, t: Term) -> Term {
+        Term::Succ(Box::new(t))
+    }
+
+    fn pred(&self, t: Term) -> Term {
+        Term::Pred(Box::new(t))
+    }
+
+    fn iszero(&self, t: Term) -> Term {
+        Term::IsZero(Box::new(t))
+    }
+
+    fn tru(&self) -> Term {
+        Term::True
+    }
+
+    fn fls(&self) -> Term {
+        Term::False
+    }
+
+    fn if_(&self, t1: Term, t2: Term, t3: Option<Term>) -> Term {
+        Term::If(Box::new(t1), Box::new(t2), t3.map(Box::new))
+    }
+}
+
+/// Evaluates to a value, or to `None` where `eval` gets stuck. Both
+/// branches of an `if` are interpreted, which is safe because arith
+/// evaluation always terminates; only the chosen one is kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eval;
+
+impl Arith for Eval {
+    type Repr = Option<Value>;
+
+    fn zero(&self) -> Option<Value> {
+        Some(Value::Nat(0))
+    }
+
+    fn succ(&self, t: Option<Value>) -> Option<Value> {
+        match t? {
+            Value::Nat(n) => Some(Value::Nat(n + 1)),
+            Value::Bool(_) => None,
+        }
+    }
+
+    fn pred(&self, t: Option<Value>) -> Option<Value> {
+        match t? {
+            Value::Nat(n) => Some(Value::Nat(n.saturating_sub(1))),
+            Value::Bool(_) => None,
+        }
+    }
+
+    fn iszero(&self, t: Option<Value>) -> Option<Value> {
+        match t? {
+            Value::Nat(n) => Some(Value::Bool(n == 0)),
+            Value::Bool(_) => None,
+        }
+    }
+
+    fn tru(&self) -> Option<Value> {
+        Some(Value::Bool(true))
+    }
+
+    fn fls(&self) -> Option<Value> {
+        Some(Value::Bool(false))
+    }
+
+    fn if_(&self, t1: Option<Value>, t2: Option<Value>, t3: Option<Option<Value>>) -> Option<Value> {
+        match t1? {
+            Value::Bool(true) => t2,
+            Value::Bool(false) => t3?,
+            Value::Nat(_) => None,
+        }
+    }
+}
+
+/// Printed concrete syntax, with what the enclosing construct needs to
+/// know to parenthesize it the way `Display` for `Term` does.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Printed {
+    text: String,
+    numeral: Option<u64>,
+    is_const: bool,
+    is_if: bool,
+}
+
+impl Printed {
+    fn new(text: String) -> Self {
+        Printed { text, numeral: None, is_const: false, is_if: false }
+    }
+
+    fn wrapped(&self, wrap: bool) -> String {
+        if wrap {
+            format!("({})", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+impl fmt::Display for Printed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// Pretty prints, producing the same text as `Display` for `Term`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Print;
+
+impl Arith for Print {
+    type Repr = Printed;
+
+    fn zero(&self) -> Printed {
+        Printed { text: String::from("0"), numeral: Some(0), is_const: true, is_if: false }
+    }
+
+    fn succ(&self, t: Printed) -> Printed {
+        match t.numeral {
+            Some(n) => Printed { text: (n + 1).to_string(), numeral: Some(n + 1), is_const: true, is_if: false },
+            None => Printed::new(format!("succ {}", t.wrapped(!t.is_const))),
+        }
+    }
+
+    fn pred(&self, t: Printed) -> Printed {
+        Printed::new(format!("pred {}", t.wrapped(!t.is_const)))
+    }
+
+    fn iszero(&self, t: Printed) -> Printed {
+        Printed::new(format!("iszero {}", t.wrapped(!t.is_const)))
+    }
+
+    fn tru(&self) -> Printed {
+        Printed { text: String::from("true"), numeral: None, is_const: true, is_if: false }
+    }
+
+    fn fls(&self) -> Printed {
+        Printed { text: String::from("false"), numeral: None, is_const: true, is_if: false }
+    }
+
+    fn if_(&self, t1: Printed, t2: Printed, t3: Option<Printed>) -> Printed {
+        let mut text = format!("if {} then {}", t1.wrapped(t1.is_if), t2.wrapped(t2.is_if));
+        if let Some(t3) = t3 {
+            text.push_str(&format!(" else {}", t3.wrapped(t3.is_if)));
+        }
+        Printed { is_if: true, ..Printed::new(text) }
+    }
+}
+
+/// Type checks with the rules of `tyarith::tyarith::type_check`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TypeCheck;
+
+impl Arith for TypeCheck {
+    type Repr = Result<Type, TyError>;
+
+    fn zero(&self) -> Result<Type, TyError> {
+        Ok(Type::Nat)
+    }
+
+    fn succ(&self, t: Result<Type, TyError>) -> Result<Type, TyError> {
+        match t {
+            Ok(Type::Nat) => Ok(Type::Nat),
+            _ => Err(TyError::TypingError),
+        }
+    }
+
+    fn pred(&self, t: Result<Type, TyError>) -> Result<Type, TyError> {
+        self.succ(t)
+    }
+
+    fn iszero(&self, t: Result<Type, TyError>) -> Result<Type, TyError> {
+        match t {
+            Ok(Type::Nat) => Ok(Type::Bool),
+            _ => Err(TyError::TypingError),
+        }
+    }
+
+    fn tru(&self) -> Result<Type, TyError> {
+        Ok(Type::Bool)
+    }
+
+    fn fls(&self) -> Result<Type, TyError> {
+        Ok(Type::Bool)
+    }
+
+    fn if_(&self, t1: Result<Type, TyError>, t2: Result<Type, TyError>, t3: Option<Result<Type, TyError>>) -> Result<Type, TyError> {
+        if t1 != Ok(Type::Bool) {
+            return Err(TyError::TypingError);
+        }
+        let ty2 = t2?;
+        match t3 {
+            Some(t3) if t3? != ty2 => Err(TyError::TypingError),
+            _ => Ok(ty2),
+        }
+    }
+}
+
+/// Counts nodes, like `metrics::size`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Size;
+
+impl Arith for Size {
+    type Repr = usize;
+
+    fn zero(&self) -> usize {
+        1
+    }
+
+    fn succ(&self, t: usize) -> usize {
+        t + 1
+    }
+
+    fn pred(&self, t: usize) -> usize {
+        t + 1
+    }
+
+    fn iszero(&self, t: usize) -> usize {
+        t + 1
+    }
+
+    fn tru(&self) -> usize {
+        1
+    }
+
+    fn fls(&self) -> usize {
+        1
+    }
+
+    fn if_(&self, t1: usize, t2: usize, t3: Option<usize>) -> usize {
+        t1 + t2 + t3.unwrap_or(0) + 1
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+use crate::arith::Term;
+
+/// The instruction set of the arith stack machine. Jump targets are
+/// absolute instruction indices.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instr {
+    PushTrue,
+    PushFalse,
+    PushZero,
+    Succ,
+    Pred,
+    IsZero,
+    // Pops a boolean and jumps if it is false.
+    JumpIfFalse(usize),
+    Jump(usize),
+    // Reached when the condition of an `if` without else is false.
+    NoElse,
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instr::PushTrue => write!(f, "push_true"),
+            Instr::PushFalse => write!(f, "push_false"),
+            Instr::PushZero => write!(f, "push_zero"),
+            Instr::Succ => write!(f, "succ"),
+            Instr::Pred => write!(f, "pred"),
+            Instr::IsZero => write!(f, "iszero"),
+            Instr::JumpIfFalse(target) => write!(f, "jump_if_false {:04}", target),
+            Instr::Jump(target) => write!(f, "jump {:04}", target),
+            Instr::NoElse => write!(f, "no_else"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Nat(u64),
+}
+
+impl Value {
+    pub fn to_term(self) -> Term {
+        match self {
+            Value::Bool(true) => Term::True,
+            Value::Bool(false) => Term::False,
+            Value::Nat(n) => (0..n).fold(Term::Zero, |t, _| Term::Succ(Box::new(t))),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Nat(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// Why the machine stopped early. `pc` is the index of the instruction
+/// that failed; these are exactly the terms `eval` leaves stuck.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VmError {
+    ExpectedNat { pc: usize, found: Value },
+    ExpectedBool { pc: usize, found: Value },
+    NoElse { pc: usize },
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VmError::ExpectedNat { pc, found } => write!(f, "{:04}: expected a number, found {}", pc, found),
+            VmError::ExpectedBool { pc, found } => write!(f, "{:04}: expected a boolean, found {}", pc, found),
+            VmError::NoElse { pc } => write!(f, "{:04}: the condition is false and there is no else branch", pc),
+        }
+    }
+}
+
+fn emit(term: &Term, code: &mut Vec<Instr>) {
+    match term {
+        Term::True => code.push(Instr::PushTrue),
+        Term::False => code.push(Instr::PushFalse),
+        Term::Zero => code.push(Instr::PushZero),
+        Term::Succ(term1) => {
+            emit(term1, code);
+            code.push(Instr::Succ);
+        }
+        Term::Pred(term1) => {
+            emit(term1, code);
+            code.push(Instr::Pred);
+        }
+        Term::IsZero(term1) => {
+            emit(term1, code);
+            code.push(Instr::IsZero);
+        }
+        Term::If(term1, term2, term3) => {
+            emit(term1, code);
+            let jump_to_else = code.len();
+            code.push(Instr::JumpIfFalse(0));
+            emit(term2, code);
+            let jump_to_end = code.len();
+            code.push(Instr::Jump(0));
+            code[jump_to_else] = Instr::JumpIfFalse(code.len());
+            match term3 {
+                Some(term3) => emit(term3, code),
+                None => code.push(Instr::NoElse),
+            }
+            code[jump_to_end] = Instr::Jump(code.len());
+        }
+    }
+}
+
+/// Compiles `term` to code that leaves its value on the stack.
+pub fn compile(term: &Term) -> Vec<Instr> {
+    let mut code = vec![];
+    emit(term, &mut code);
+    code
+}
+
+/// One instruction per line, prefixed with its index.
+pub fn disassemble(code: &[Instr]) -> String {
+    code.iter().enumerate().map(|(pc, instr)| format!("{:04}  {}\n", pc, instr)).collect()
+}
+
+/// Runs compiled code to completion and returns the value it leaves.
+pub fn run(code: &[Instr]) -> Result<Value, VmError> {
+    let mut stack: Vec<Value> = vec![];
+    let mut pc = 0;
+    while pc < code.len() {
+        let instr = code[pc];
+        pc += 1;
+        match instr {
+            Instr::PushTrue => stack.push(Value::Bool(true)),
+            Instr::PushFalse => stack.push(Value::Bool(false)),
+            Instr::PushZero => stack.push(Value::Nat(0)),
+            Instr::Succ | Instr::Pred | Instr::IsZero => {
+                let n = match stack.pop() {
+                    Some(Value::Nat(n)) => n,
+                    Some(found) => return Err(VmError::ExpectedNat { pc: pc - 1, found }),
+                    None => panic!("stack underflow at {:04}", pc - 1),
+                };
+                stack.push(match instr {
+                    Instr::Succ => Value::Nat(n + 1),
+                    Instr::Pred => Value::Nat(n.saturating_sub(1)),
+                    _ => Value::Bool(n == 0),
+                });
+            }
+            Instr::JumpIfFalse(target) => match stack.pop() {
+                Some(Value::Bool(true)) => {}
+                Some(Value::Bool(false)) => pc = target,
+                Some(found) => return Err(VmError::ExpectedBool { pc: pc - 1, found }),
+                None => panic!("stack underflow at {:04}", pc - 1),
+            },
+            Instr::Jump(target) => pc = target,
+            Instr::NoElse => return Err(VmError::NoElse { pc: pc - 1 }),
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(value), true) => Ok(value),
+        _ => panic!("compiled code must leave exactly one value"),
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+use crate::arith::{self, classify, Classification};
+
+/// Arith terms with the explicit `wrong` of TAPL exercise 3.5.16, which
+/// stuck terms evaluate to instead of stopping.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    Wrong,
+    True,
+    False,
+    Zero,
+    IsZero(Box<Term>),
+    If(Box<Term>, Box<Term>, Option<Box<Term>>),
+    Succ(Box<Term>),
+    Pred(Box<Term>),
+}
+
+impl From<&arith::Term> for Term {
+    fn from(t: &arith::Term) -> Self {
+        match t {
+            arith::Term::True => Term::True,
+            arith::Term::False => Term::False,
+            arith::Term::Zero => Term::Zero,
+            arith::Term::IsZero(t1) => Term::IsZero(Box::new(Term::from(&**t1))),
+            arith::Term::If(t1, t2, t3) => Term::If(
+                Box::new(Term::from(&**t1)),
+                Box::new(Term::from(&**t2)),
+                t3.as_ref().map(|t3| Box::new(Term::from(&**t3))),
+            ),
+            arith::Term::Succ(t1) => Term::Succ(Box::new(Term::from(&**t1))),
+            arith::Term::Pred(t1) => Term::Pred(Box::new(Term::from(&**t1))),
+        }
+    }
+}
+
+// Every subterm but the constants is parenthesized; `wrong` has no concrete
+// syntax, so this is for reading, not for the parser.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sub = |t: &Term| match t {
+            Term::Wrong | Term::True | Term::False | Term::Zero => t.to_string(),
+            _ => format!("({})", t),
+        };
+        match self {
+            Term::Wrong => write!(f, "wrong"),
+            Term::True => write!(f, "true"),
+            Term::False => write!(f, "false"),
+            Term::Zero => write!(f, "0"),
+            Term::Succ(t1) => write!(f, "succ {}", sub(t1)),
+            Term::Pred(t1) => write!(f, "pred {}", sub(t1)),
+            Term::IsZero(t1) => write!(f, "iszero {}", sub(t1)),
+            Term::If(t1, t2, None) => write!(f, "if {} then {}", sub(t1), sub(t2)),
+            Term::If(t1, t2, Some(t3)) => write!(f, "if {} then {} else {}", sub(t1), sub(t2), sub(t3)),
+        }
+    }
+}
+
+fn is_numeric_val(t: &Term) -> bool {
+    match t {
+        Term::Zero => true,
+        Term::Succ(t1) => is_numeric_val(t1),
+        _ => false,
+    }
+}
+
+// `badnat ::= wrong | true | false`
+fn is_bad_nat(t: &Term) -> bool {
+    matches!(t, Term::Wrong | Term::True | Term::False)
+}
+
+// `badbool ::= wrong | nv`
+fn is_bad_bool(t: &Term) -> bool {
+    matches!(t, Term::Wrong) || is_numeric_val(t)
+}
+
+/// One step of the semantics of exercise 3.5.16: the rules of figures 3-1
+/// and 3-2 plus E-If-Wrong, E-Succ-Wrong, E-Pred-Wrong and E-IsZero-Wrong.
+/// `if false then t` with no else steps to `wrong` as well, since `eval`
+/// is stuck on it. Returns `None` on values and `wrong`.
+pub fn eval1(term: &Term) -> Option<Term> {
+    match term {
+        Term::If(t1, t2, t3) => match **t1 {
+            Term::True => Some((**t2).clone()),
+            Term::False => Some(t3.as_ref().map_or(Term::Wrong, |t3| (**t3).clone())),
+            ref t1 if is_bad_bool(t1) => Some(Term::Wrong),
+            ref t1 => eval1(t1).map(|t1| Term::If(Box::new(t1), t2.clone(), t3.clone())),
+        },
+        Term::Succ(t1) if is_bad_nat(t1) => Some(Term::Wrong),
+        Term::Succ(t1) => eval1(t1).map(|t1| Term::Succ(Box::new(t1))),
+        Term::Pred(t1) => match **t1 {
+            Term::Zero => Some(Term::Zero),
+            Term::Succ(ref nv) if is_numeric_val(nv) => Some((**nv).clone()),
+            ref t1 if is_bad_nat(t1) => Some(Term::Wrong),
+            ref t1 => eval1(t1).map(|t1| Term::Pred(Box::new(t1))),
+        },
+        Term::IsZero(t1) => match **t1 {
+            Term::Zero => Some(Term::True),
+            Term::Succ(ref nv) if is_numeric_val(nv) => Some(Term::False),
+            ref t1 if is_bad_nat(t1) => Some(Term::Wrong),
+            ref t1 => eval1(t1).map(|t1| Term::IsZero(Box::new(t1))),
+        },
+        _ => None,
+    }
+}
+
+/// Evaluates `term` to a value or to `wrong`; these semantics have no
+/// other normal forms.
+pub fn eval(term: Term) -> Term {
+    let mut term = term;
+    while let Some(next) = eval1(&term) {
+        term = next;
+    }
+    term
+}
+
+/// Checks that the two semantics agree on `term`: it gets stuck under
+/// `arith::eval` exactly when it evaluates to `wrong` here, and otherwise
+/// both reach the same value.
+pub fn agrees(term: &arith::Term) -> bool {
+    match (classify(term.clone()), eval(Term::from(term))) {
+        (Classification::Stuck { .. }, Term::Wrong) => true,
+        (Classification::Numeric(v), w) | (Classification::Boolean(v), w) => Term::from(&v) == w,
+        _ => false,
+    }
+}
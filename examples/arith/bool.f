@@ -0,0 +1,4 @@
+true;
+if false then true else false;
+iszero (pred (succ zero));
+if iszero zero then succ zero else zero;
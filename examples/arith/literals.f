@@ -0,0 +1,4 @@
+pred 3;
+iszero (pred 1);
+if iszero 2 then 0 else succ 41;
+succ (if true then 9 else 0);
@@ -0,0 +1,3 @@
+succ (succ zero);
+pred (pred (succ (succ zero)));
+pred zero;
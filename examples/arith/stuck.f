@@ -0,0 +1,3 @@
+succ (pred true);
+if succ zero then true else false;
+iszero (if false then zero);
@@ -0,0 +1,3 @@
+(λ x. x) true;
+if true then false else true;
+iszero zero;
@@ -0,0 +1,4 @@
+(λ x:Bool. if x then false else true) true;
+λ x:Bool. x;
+if true then false else (λ x:Bool. x);
+(λ f:Bool. f) (λ x:Bool. x);
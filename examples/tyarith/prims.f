@@ -0,0 +1,5 @@
+plus 2 (times 3 4);
+minus 2 5;
+if lt 1 2 then times 1000000000 1000000000 else 0;
+eq (succ 2) 3;
+plus true 1
@@ -0,0 +1,4 @@
+iszero (succ zero);
+if true then pred zero else succ zero;
+succ true;
+if zero then true else false;
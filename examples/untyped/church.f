@@ -0,0 +1,3 @@
+(λ x. x) (λ y. y);
+(λ x. (x x)) (λ x. x);
+((λ x. (λ y. (y x))) (λ x. x)) (λ x. (λ y. (y x)));
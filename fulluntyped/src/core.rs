@@ -10,7 +10,26 @@ fn is_numeric_val(t: &Term) -> bool {
     }
 }
 
-fn eval1(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
+// The application rules, matching on a reference so that only the
+// subterms that are kept get copied.
+fn eval1_app(term1: &Term, term2: &Term, ctx: &Context) -> Result<Term, EvalError> {
+    if is_val(term2) {
+        if let Term::TmAbs(_, t1) = term1 {
+            Ok(term_subst_top(term2, t1.as_ref()))
+        } else {
+            Ok(Term::TmApp(Box::new(eval1(term1, ctx)?), Box::new(term2.clone())))
+        }
+    } else if is_val(term1) {
+        Ok(Term::TmApp(Box::new(term1.clone()), Box::new(eval1(term2, ctx)?)))
+    } else {
+        Ok(Term::TmApp(Box::new(eval1(term1, ctx)?), Box::new(term2.clone())))
+    }
+}
+
+pub fn eval1(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
+    if let Term::TmApp(term1, term2) = t {
+        return eval1_app(term1, term2, ctx);
+    }
     match t.to_owned() {
         Term::If(term1, term2, term3) => {
             match *term1 {
@@ -64,19 +83,6 @@ fn eval1(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
                 _ => Err(EvalError::NoRule(*term1))
             }
         }
-        Term::TmApp(term1, term2) if is_val(&term2) => {
-            if let Term::TmAbs(_, t1) = *term1 {
-                Ok(term_subst_top(term2.as_ref(), t1.as_ref()))
-            } else {
-                Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2))
-            }
-        }
-        Term::TmApp(term1, term2) if is_val(&term1) => {
-            Ok(Term::TmApp(term1, Box::new(eval1(term2.as_ref(), ctx)?)))
-        }
-        Term::TmApp(term1, term2) => {
-            Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2))
-        }
         _ => Err(EvalError::NoRule(t.to_owned()))
     }
     //unimplemented!()
@@ -24,6 +24,7 @@ pub enum Token {
     Dot,      //.
     LParen,   //(
     RParen,   //)
+    Semi,     //;
 }
 
 
@@ -138,10 +139,14 @@ impl<T> Lexer<T>
                     self.result.push(Token::Dot);
                     self.next_char();
                 }
-                ' ' => {
+                ';' => {
+                    self.result.push(Token::Semi);
+                    self.next_char();
+                }
+                ' ' | '\t' | '\n' | '\r' => {
                     self.next_char();
                 },
-                _ => {}
+                c => panic!("Unknown character `{}`~~", c),
             }
         }
     }
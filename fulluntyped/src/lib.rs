@@ -0,0 +1,5 @@
+pub mod lexer;
+pub mod syntax;
+pub mod parser;
+pub mod core;
+pub mod builtin;
@@ -171,6 +171,29 @@ impl<T> Parser<T>
         self.term()
     }
 
+    pub fn parse_commands(&mut self) -> Vec<Term> {
+        let mut result = vec![];
+        loop {
+            match self.tok0 {
+                None => break,
+                Some(Token::Semi) => {
+                    self.next_token();
+                }
+                _ => {
+                    match self.parse() {
+                        Some(term) => result.push(term),
+                        None => panic!("Err in command~~"),
+                    }
+                    match self.tok0 {
+                        None | Some(Token::Semi) => {}
+                        _ => panic!("Expected `;`~~"),
+                    }
+                }
+            }
+        }
+        result
+    }
+
     fn next_token(&mut self) -> Option<Token> {
         let tmp_token = self.tok0.clone();
         self.tok0 = self.token.next();
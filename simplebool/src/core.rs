@@ -18,7 +18,7 @@ impl fmt::Display for Type {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Context {
     contexts: Vec<(String, Type)>
 }
@@ -165,3 +165,105 @@ pub fn type_of(t: &Term, ctx: &Context) -> Result<Type, TypeError> {
     }
 }
 
+
+fn shift_walk(d: isize, c: isize, t: &Term) -> Term {
+    match t.to_owned() {
+        Term::TmVar(idx, n) => {
+            if idx >= c {
+                Term::TmVar(idx + d, n + d)
+            } else {
+                Term::TmVar(idx, n + d)
+            }
+        }
+        Term::TmAbs(name, ty, term) => {
+            Term::TmAbs(name, ty, Box::new(shift_walk(d, c + 1, term.as_ref())))
+        }
+        Term::TmApp(term1, term2) => {
+            Term::TmApp(Box::new(shift_walk(d, c, term1.as_ref())), Box::new(shift_walk(d, c, term2.as_ref())))
+        }
+        Term::TmTrue => Term::TmTrue,
+        Term::TmFalse => Term::TmFalse,
+        Term::TmIf(term1, term2, term3) => Term::TmIf(
+            Box::new(shift_walk(d, c, term1.as_ref())),
+            Box::new(shift_walk(d, c, term2.as_ref())),
+            Box::new(shift_walk(d, c, term3.as_ref())),
+        ),
+    }
+}
+
+fn shift(d: isize, t: &Term) -> Term {
+    shift_walk(d, 0, t)
+}
+
+fn subst_walk(j: isize, s: &Term, c: isize, t: &Term) -> Term {
+    match t.to_owned() {
+        Term::TmVar(idx, n) => {
+            if idx == j + c {
+                shift(c, s)
+            } else {
+                Term::TmVar(idx, n)
+            }
+        }
+        Term::TmAbs(name, ty, term) => Term::TmAbs(name, ty, Box::new(subst_walk(j, s, c + 1, term.as_ref()))),
+        Term::TmApp(term1, term2) => Term::TmApp(
+            Box::new(subst_walk(j, s, c, term1.as_ref())),
+            Box::new(subst_walk(j, s, c, term2.as_ref())),
+        ),
+        Term::TmTrue => Term::TmTrue,
+        Term::TmFalse => Term::TmFalse,
+        Term::TmIf(term1, term2, term3) => Term::TmIf(
+            Box::new(subst_walk(j, s, c, term1.as_ref())),
+            Box::new(subst_walk(j, s, c, term2.as_ref())),
+            Box::new(subst_walk(j, s, c, term3.as_ref())),
+        ),
+    }
+}
+
+fn subst(j: isize, s: &Term, t: &Term) -> Term {
+    subst_walk(j, s, 0, t)
+}
+
+fn term_subst_top(s: &Term, t: &Term) -> Term {
+    shift(-1, subst(0, shift(1, s).borrow(), t).borrow())
+}
+
+fn is_val(t: &Term) -> bool {
+    matches!(t, Term::TmAbs(_, _, _) | Term::TmTrue | Term::TmFalse)
+}
+
+#[derive(Debug)]
+pub enum EvalError {
+    NoRule(Term),
+}
+
+pub fn eval1(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
+    match t.to_owned() {
+        Term::TmApp(term1, term2) if is_val(&term1) && is_val(&term2) => {
+            match *term1 {
+                Term::TmAbs(_, _, t12) => Ok(term_subst_top(term2.as_ref(), t12.as_ref())),
+                _ => Err(EvalError::NoRule(t.to_owned())),
+            }
+        }
+        Term::TmApp(term1, term2) if is_val(&term1) => {
+            Ok(Term::TmApp(term1, Box::new(eval1(term2.as_ref(), ctx)?)))
+        }
+        Term::TmApp(term1, term2) => {
+            Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2))
+        }
+        Term::TmIf(term1, term2, term3) => {
+            match *term1 {
+                Term::TmTrue => Ok(*term2),
+                Term::TmFalse => Ok(*term3),
+                _ => Ok(Term::TmIf(Box::new(eval1(term1.as_ref(), ctx)?), term2, term3)),
+            }
+        }
+        _ => Err(EvalError::NoRule(t.to_owned()))
+    }
+}
+
+pub fn eval(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
+    match eval1(t, ctx) {
+        Ok(t) => eval(t.borrow(), ctx),
+        _ => Ok(t.to_owned())
+    }
+}
@@ -1,13 +1,14 @@
 #[cfg(test)]
 mod tests {
     use crate::parser::{Lexer, Parser};
-    use crate::core::{type_of, Context, TypeError};
+    use crate::core::{type_of, eval, Context, ContextTerm, TypeError};
 
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
 
+    #[test]
     fn type_check() {
         let tmp_str = "if true then false else (λ x:Bool. x)";
         let mut lex = Lexer::new(tmp_str.chars());
@@ -17,6 +18,18 @@ mod tests {
         let out = parser.parse();
         assert_eq!(type_of(&out.unwrap(), &Context::new()), Err(TypeError::TyErr));
     }
+
+    #[test]
+    fn eval_test() {
+        let tmp_str = "(λ x:Bool. if x then false else true) true";
+        let mut lex = Lexer::new(tmp_str.chars());
+        lex.lex_input();
+
+        let mut parser = Parser::new(lex.result.into_iter());
+        let out = parser.parse().unwrap();
+        let value = eval(&out, &parser.ctx).unwrap();
+        assert_eq!(format!("{}", ContextTerm::new(&parser.ctx, &value)), "false");
+    }
 }
 pub mod parser;
 pub mod core;
\ No newline at end of file
@@ -19,6 +19,7 @@ pub enum Token {
     Dot,
     LParen,
     RParen,
+    Semi,
 }
 
 
@@ -40,9 +41,10 @@ pub struct Lexer<T: Iterator<Item = char> + Debug> {
     chr0: Option<char>,
     chr1: Option<char>,
     keywords: HashMap<String, Token>,
+    offset: usize,
 
     pub result: Vec<Token>,
-
+    pub spans: Vec<(usize, usize)>,
 }
 
 
@@ -55,13 +57,21 @@ impl<T> Lexer<T>
             chr0: None,
             chr1: None,
             keywords: get_keywords(),
-            result: vec![]
+            offset: 0,
+            result: vec![],
+            spans: vec![]
         };
         lex.next_char();
         lex.next_char();
+        lex.offset = 0;
         lex
     }
 
+    // Character offset of the next unread character.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
     pub fn lex_input(&mut self) {
         //(lambda x: . x) (lambda x. x x);
         loop {
@@ -69,6 +79,8 @@ impl<T> Lexer<T>
                 break;
             }
 
+            let start = self.offset;
+            let len = self.result.len();
             match self.chr0.unwrap() {
                 'a'..='z' | 'A'..='Z' => {
                     let mut result = Vec::new();
@@ -107,10 +119,17 @@ impl<T> Lexer<T>
                     self.result.push(Token::Dot);
                     self.next_char();
                 }
-                ' ' => {
+                ';' => {
+                    self.result.push(Token::Semi);
+                    self.next_char();
+                }
+                ' ' | '\t' | '\n' | '\r' => {
                     self.next_char();
                 },
-                _ => {}
+                c => panic!("Unknown character `{}`~~", c),
+            }
+            if self.result.len() > len {
+                self.spans.push((start, self.offset));
             }
         }
     }
@@ -119,17 +138,29 @@ impl<T> Lexer<T>
         let tmp_char = self.chr0;
         self.chr0 = self.chr1;
         self.chr1 = self.chars.next();
+        self.offset += 1;
 
         tmp_char
     }
 }
 
+// A subterm the parser produced, covering tokens `start..end`, together
+// with the context it was parsed in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub start: usize,
+    pub end: usize,
+    pub term: Term,
+    pub ctx: Context,
+}
 
 #[derive(PartialEq)]
 pub struct Parser<T: Iterator<Item = Token> + Debug + Clone> {
     pub ctx: Context,
     token: T,
     tok0: Option<Token>,
+    pos: usize,
+    pub nodes: Vec<Node>,
     pub result: Option<Term>
 }
 
@@ -141,12 +172,27 @@ impl<T> Parser<T>
             ctx: Context::new(),
             token: input,
             tok0: None,
+            pos: 0,
+            nodes: vec![],
             result: None
         };
         parser.next_token();
+        parser.pos = 0;
         parser
     }
 
+    // Index of the current token.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn mark(&mut self, start: usize, term: Option<Term>) -> Option<Term> {
+        if let Some(ref term) = term {
+            self.nodes.push(Node { start, end: self.pos, term: term.clone(), ctx: self.ctx.clone() });
+        }
+        term
+    }
+
     fn lambda(&mut self) -> Option<Term> {
         self.next_token(); //lambda
         let prev_ctx = self.ctx.to_owned();
@@ -179,14 +225,16 @@ impl<T> Parser<T>
     }
 
     fn term(&mut self) -> Option<Term> {
-        match self.tok0 {
+        let start = self.pos;
+        let term = match self.tok0 {
             Some(Token::Lambda) => {
                 self.lambda()
             }
             _ => {
                 self.application()
             }
-        }
+        };
+        self.mark(start, term)
     }
 
     pub fn parse_if_then_expr(&mut self) -> Option<Term> {
@@ -205,20 +253,29 @@ impl<T> Parser<T>
     }
 
     fn atom(&mut self) -> Option<Term> {
+        let start = self.pos;
+        let term = self.atom_term();
+        self.mark(start, term)
+    }
+
+    fn atom_term(&mut self) -> Option<Term> {
         match self.tok0.clone() {
             Some(Token::LParen) => {
                 self.next_token();
                 let term = self.term();
+                if self.tok0 != Some(Token::RParen) {
+                    panic!("Expected `)`~~");
+                }
                 self.next_token();
                 term
             }
             Some(Token::Var(var_name)) => {
-                self.next_token();
                 match self.ctx.name_to_index(var_name.as_ref()) {
                     Some(idx) => {
+                        self.next_token();
                         Some(Term::TmVar(idx, self.ctx.len()))
                     }
-                    _ => panic!("Unbound variable~~")
+                    _ => panic!("Unbound variable `{}`~~", var_name)
                 }
             }
             Some(Token::True) => {
@@ -237,9 +294,11 @@ impl<T> Parser<T>
     }
 
     fn application(&mut self) -> Option<Term> {
+        let start = self.pos;
         if let Some(mut lhs) = self.atom() {
             while let Some(rhs) = self.atom() {
                 lhs = Term::TmApp(Box::new(lhs), Box::new(rhs));
+                self.mark(start, Some(lhs.clone()));
             }
             return Some(lhs);
         }
@@ -250,9 +309,33 @@ impl<T> Parser<T>
         self.term()
     }
 
+    pub fn parse_commands(&mut self) -> Vec<Term> {
+        let mut result = vec![];
+        loop {
+            match self.tok0 {
+                None => break,
+                Some(Token::Semi) => {
+                    self.next_token();
+                }
+                _ => {
+                    match self.parse() {
+                        Some(term) => result.push(term),
+                        None => panic!("Err in command~~"),
+                    }
+                    match self.tok0 {
+                        None | Some(Token::Semi) => {}
+                        _ => panic!("Expected `;`~~"),
+                    }
+                }
+            }
+        }
+        result
+    }
+
     fn next_token(&mut self) -> Option<Token> {
         let tmp_token = self.tok0.clone();
         self.tok0 = self.token.next();
+        self.pos += 1;
 
         tmp_token
     }
@@ -0,0 +1,282 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+use arith::generator::Generator;
+use tyarith::rng::Rng;
+use tyarith::tyarith::Type;
+use crate::lang::{guard, Lang};
+
+// Terms that take more steps than this are treated as divergent and left
+// out of the corpus, as are terms on which `eval1` panics.
+const MAX_STEPS: usize = 100_000;
+// Every evaluator runs over the corpus until at least this much time has
+// passed, so the rates are not dominated by timer resolution.
+const MIN_TIME: Duration = Duration::from_millis(200);
+const CORPUS_SIZE: usize = 200;
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+// `untyped::core::eval` recurses once per step.
+const STACK_SIZE: usize = 1 << 30;
+
+// How many threads are inside `allocations`. Outside of it the allocator
+// does no accounting and costs one atomic load per call.
+static MEASURING: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+    static LIVE: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record(allocs: usize, bytes: isize) {
+    if MEASURING.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let _ = ALLOCS.try_with(|a| a.set(a.get() + allocs));
+    let _ = LIVE.try_with(|live| {
+        live.set(live.get() + bytes);
+        let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+    });
+}
+
+/// The system allocator, counting allocations and live bytes per thread
+/// while `tapl bench` measures an evaluator.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(1, layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record(0, -(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(1, new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+// Returns the allocations made by `f` and the most heap it had live at
+// any point beyond what was live when it started.
+fn allocations<F: FnOnce()>(f: F) -> (usize, usize) {
+    let (allocs, live) = (ALLOCS.with(|a| a.get()), LIVE.with(|l| l.get()));
+    PEAK.with(|p| p.set(live));
+    MEASURING.fetch_add(1, Ordering::Relaxed);
+    f();
+    MEASURING.fetch_sub(1, Ordering::Relaxed);
+    (ALLOCS.with(|a| a.get()) - allocs, (PEAK.with(|p| p.get()) - live) as usize)
+}
+
+/// Well-typed arith terms from `arith::generator`, so they evaluate to a
+/// value rather than getting stuck.
+pub fn arith_corpus(size: usize) -> String {
+    let mut gen = Generator::new(SEED);
+    (0..size).map(|i| gen.typed(if i % 2 == 0 { Type::Nat } else { Type::Bool }, 7).to_string())
+        .collect::<Vec<String>>().join(";\n")
+}
+
+fn church(n: usize) -> String {
+    format!("(λ s. λ z. {}z{})", "s (".repeat(n), ")".repeat(n))
+}
+
+/// Church numerals applied to each other, whose call-by-value reduction
+/// takes roughly the product of the numerals' sizes in steps.
+pub fn untyped_corpus(size: usize) -> String {
+    let mut rng = Rng::new(SEED);
+    (0..size).map(|_| {
+        let (n, m) = (rng.below(8), rng.below(8));
+        format!("{} ({} (λ x. x)) (λ y. y)", church(n), church(m))
+    }).collect::<Vec<String>>().join(";\n")
+}
+
+// A term of the corpus with what its `eval1` trace says about it.
+struct Case<T> {
+    term: T,
+    steps: usize,
+    peak_size: usize,
+}
+
+fn untyped_size(t: &untyped::core::Term) -> usize {
+    use untyped::core::Term;
+
+    match t {
+        Term::TmVar(_, _) => 1,
+        Term::TmAbs(_, t1) => 1 + untyped_size(t1),
+        Term::TmApp(t1, t2) => 1 + untyped_size(t1) + untyped_size(t2),
+    }
+}
+
+fn trace<T: Clone, S, Z>(term: T, step: S, size: Z) -> Option<Case<T>>
+where
+    S: Fn(&T) -> Option<T>,
+    Z: Fn(&T) -> usize,
+{
+    let mut t = term.clone();
+    let mut peak_size = size(&t);
+    for steps in 0..MAX_STEPS {
+        match guard(|| Ok(step(&t))) {
+            Ok(Some(next)) => {
+                peak_size = peak_size.max(size(&next));
+                t = next;
+            }
+            Ok(None) => return Some(Case { term, steps, peak_size }),
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Row {
+    pub evaluator: &'static str,
+    pub terms: usize,
+    pub steps: usize,
+    pub time: Duration,
+    // `None` for evaluators that never build the intermediate terms.
+    pub peak_size: Option<usize>,
+    pub allocs: usize,
+    pub peak_heap: usize,
+}
+
+impl Row {
+    fn steps_per_sec(&self) -> f64 {
+        self.steps as f64 / self.time.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+}
+
+fn time<T: Clone, E: Fn(T)>(evaluator: &'static str, cases: &[Case<T>], small_step: bool, eval: E) -> Row {
+    let (allocs, peak_heap) = allocations(|| cases.iter().for_each(|c| eval(c.term.clone())));
+    let start = Instant::now();
+    let mut passes = 0;
+    while passes == 0 || start.elapsed() < MIN_TIME {
+        cases.iter().for_each(|c| eval(c.term.clone()));
+        passes += 1;
+    }
+    Row {
+        evaluator,
+        terms: cases.len(),
+        steps: cases.iter().map(|c| c.steps).sum(),
+        time: start.elapsed() / passes,
+        peak_size: if small_step { cases.iter().map(|c| c.peak_size).max() } else { None },
+        allocs,
+        peak_heap,
+    }
+}
+
+/// Parses `source` as a corpus of `lang` terms and times each of the
+/// language's evaluators on it. Steps are counted with `eval1` for every
+/// evaluator so their rates can be compared. Terms that diverge or crash
+/// are left out. Returns the rows and the number of terms in `source`.
+pub fn bench(lang: Lang, source: &str) -> Result<(Vec<Row>, usize), String> {
+    match lang {
+        Lang::Arith => {
+            let terms = guard(|| {
+                let mut lex = arith::parser::Lexer::new(source.chars());
+                lex.lex_input();
+                Ok(arith::parser::Parser::new(lex.result.into_iter()).parse_commands())
+            })?;
+            let total = terms.len();
+            let cases: Vec<Case<arith::arith::Term>> = terms.into_iter()
+                .filter_map(|t| trace(t, |t| arith::arith::eval1(t.clone()).ok(), arith::metrics::size))
+                .collect();
+            let rows = vec![
+                time("arith::arith::eval", &cases, true, |t| drop(arith::arith::eval(t))),
+                time("arith::arith::big_eval", &cases, false, |t| drop(arith::arith::big_eval(t))),
+                time("arith::arith::eval_iterative", &cases, false, |t| drop(arith::arith::eval_iterative(t))),
+            ];
+            Ok((rows, total))
+        }
+        Lang::Untyped => {
+            let ctx = untyped::core::Context::new();
+            let terms = guard(|| {
+                let mut lex = untyped::parser::Lexer::new(source.chars());
+                lex.lex_input();
+                Ok(untyped::parser::Parser::new(lex.result.into_iter()).parse_commands())
+            })?;
+            let total = terms.len();
+            let cases: Vec<Case<untyped::core::Term>> = terms.into_iter()
+                .filter_map(|t| trace(t, |t| untyped::core::eval1(t, &ctx).ok(), untyped_size))
+                .collect();
+            let rows = vec![time("untyped::core::eval", &cases, true, |t| drop(untyped::core::eval(&t, &ctx)))];
+            Ok((rows, total))
+        }
+        _ => Err(format!("bench supports arith and untyped, not {}", lang)),
+    }
+}
+
+fn bytes(n: usize) -> String {
+    if n < 1024 {
+        format!("{} B", n)
+    } else {
+        format!("{:.1} KiB", n as f64 / 1024.0)
+    }
+}
+
+fn report(name: &str, source: &str, lang: Lang) -> Result<Vec<String>, String> {
+    let (rows, total) = bench(lang, source)?;
+    let mut out = vec![
+        format!("{}: {} terms, {} left out", name, total, total - rows.first().map_or(0, |r| r.terms)),
+        format!("{:<24} {:>6} {:>8} {:>12} {:>14} {:>10} {:>12} {:>10}",
+            "evaluator", "terms", "steps", "time/pass", "steps/s", "peak size", "allocs/term", "peak heap"),
+    ];
+    for row in rows {
+        out.push(format!("{:<24} {:>6} {:>8} {:>12} {:>14.0} {:>10} {:>12.1} {:>10}",
+            row.evaluator,
+            row.terms,
+            row.steps,
+            format!("{:.3?}", row.time),
+            row.steps_per_sec(),
+            row.peak_size.map_or_else(|| String::from("-"), |s| s.to_string()),
+            row.allocs as f64 / row.terms.max(1) as f64,
+            bytes(row.peak_heap)));
+    }
+    Ok(out)
+}
+
+/// Implements `tapl bench`: benchmarks the terms in `path` as `lang`, or
+/// the generated arith and untyped corpora when no file is given.
+pub fn run(lang: Lang, path: Option<&str>) -> bool {
+    let inputs = match path {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(source) => vec![(path.to_string(), source, lang)],
+            Err(err) => {
+                eprintln!("error: cannot read {}: {}", path, err);
+                return false;
+            }
+        },
+        None => vec![
+            (String::from("generated arith"), arith_corpus(CORPUS_SIZE), Lang::Arith),
+            (String::from("generated untyped"), untyped_corpus(CORPUS_SIZE), Lang::Untyped),
+        ],
+    };
+
+    let worker = thread::Builder::new().stack_size(STACK_SIZE).spawn(move || {
+        let mut ok = true;
+        for (i, (name, source, lang)) in inputs.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            match report(name, source, *lang) {
+                Ok(lines) => lines.iter().for_each(|l| println!("{}", l)),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    ok = false;
+                }
+            }
+        }
+        ok
+    });
+    match worker.map(|w| w.join()) {
+        Ok(Ok(ok)) => ok,
+        _ => {
+            eprintln!("error: benchmark thread failed");
+            false
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use arith::vm;
+use crate::lang::{guard, Lang};
+
+/// Implements `tapl compile`: the stack-machine code of every command of
+/// `source`, followed by the value the VM computes for it.
+pub fn listing(lang: Lang, source: &str) -> Result<Vec<String>, String> {
+    if lang != Lang::Arith {
+        return Err(format!("compile supports arith, not {}", lang));
+    }
+    let terms = guard(|| {
+        let mut lex = arith::parser::Lexer::new(source.chars());
+        lex.lex_input();
+        Ok(arith::parser::Parser::new(lex.result.into_iter()).parse_commands())
+    })?;
+    Ok(terms.iter().map(|t| {
+        let code = vm::compile(t);
+        let result = match vm::run(&code) {
+            Ok(value) => format!("; {} => {}\n", t, value),
+            Err(err) => format!("; {} => error at {}\n", t, err),
+        };
+        format!("{}{}", vm::disassemble(&code), result)
+    }).collect())
+}
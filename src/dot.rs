@@ -0,0 +1,277 @@
+use std::fmt::Write;
+use crate::lang::{guard, Lang};
+
+// More steps than any teaching diagram needs; also stops divergent
+// untyped terms.
+const MAX_STEPS: usize = 100;
+
+const REDEX_COLOR: &str = "#ffd27f";
+
+/// A syntax tree reduced to what the diagram shows: a label per node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tree {
+    pub label: String,
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    fn new(label: &str, children: Vec<Tree>) -> Self {
+        Tree { label: label.to_string(), children }
+    }
+}
+
+pub fn arith_tree(t: &arith::arith::Term) -> Tree {
+    use arith::arith::Term;
+
+    match t {
+        Term::True => Tree::new("true", vec![]),
+        Term::False => Tree::new("false", vec![]),
+        Term::Zero => Tree::new("0", vec![]),
+        Term::Succ(t1) => Tree::new("succ", vec![arith_tree(t1)]),
+        Term::Pred(t1) => Tree::new("pred", vec![arith_tree(t1)]),
+        Term::IsZero(t1) => Tree::new("iszero", vec![arith_tree(t1)]),
+        Term::If(t1, t2, t3) => {
+            let mut children = vec![arith_tree(t1), arith_tree(t2)];
+            if let Some(t3) = t3 {
+                children.push(arith_tree(t3));
+            }
+            Tree::new("if", children)
+        }
+    }
+}
+
+/// Variables are labelled with their restored name and de Bruijn index.
+pub fn untyped_tree(t: &untyped::core::Term, ctx: &untyped::core::Context) -> Tree {
+    use untyped::core::Term;
+
+    match t {
+        Term::TmVar(idx, _) => {
+            let name = ctx.index_to_name(*idx).unwrap_or_else(|| String::from("?"));
+            Tree::new(&format!("{} #{}", name, idx), vec![])
+        }
+        Term::TmAbs(name, t1) => {
+            let (ctx1, name) = ctx.pick_fresh_name(name);
+            Tree::new(&format!("λ{}", name), vec![untyped_tree(t1, &ctx1)])
+        }
+        Term::TmApp(t1, t2) => Tree::new("app", vec![untyped_tree(t1, ctx), untyped_tree(t2, ctx)]),
+    }
+}
+
+pub fn simplebool_tree(t: &simplebool::core::Term, ctx: &simplebool::core::Context) -> Tree {
+    use simplebool::core::Term;
+
+    match t {
+        Term::TmVar(idx, _) => {
+            let name = ctx.index_to_name(*idx).unwrap_or_else(|| String::from("?"));
+            Tree::new(&format!("{} #{}", name, idx), vec![])
+        }
+        Term::TmAbs(name, ty, t1) => {
+            let (ctx1, name) = ctx.pick_fresh_name(name, ty.clone());
+            Tree::new(&format!("λ{}:{}", name, ty), vec![simplebool_tree(t1, &ctx1)])
+        }
+        Term::TmApp(t1, t2) => Tree::new("app", vec![simplebool_tree(t1, ctx), simplebool_tree(t2, ctx)]),
+        Term::TmTrue => Tree::new("true", vec![]),
+        Term::TmFalse => Tree::new("false", vec![]),
+        Term::TmIf(t1, t2, t3) => {
+            Tree::new("if", vec![simplebool_tree(t1, ctx), simplebool_tree(t2, ctx), simplebool_tree(t3, ctx)])
+        }
+    }
+}
+
+// The `*_redex` functions follow the evaluation contexts of each crate's
+// `eval1` down to the subterm it contracts, returning the child indices on
+// the way there. They are only called on terms `eval1` can step.
+
+fn arith_redex(t: &arith::arith::Term) -> Vec<usize> {
+    use arith::arith::Term;
+
+    fn is_numeric_val(t: &Term) -> bool {
+        match t {
+            Term::Zero => true,
+            Term::Succ(t1) => is_numeric_val(t1),
+            _ => false,
+        }
+    }
+
+    let inner = match t {
+        Term::If(t1, _, _) if matches!(**t1, Term::True | Term::False) => return vec![],
+        Term::IsZero(t1) | Term::Pred(t1) if is_numeric_val(t1) => return vec![],
+        Term::If(t1, _, _) | Term::IsZero(t1) | Term::Pred(t1) | Term::Succ(t1) => t1,
+        _ => return vec![],
+    };
+    let mut path = vec![0];
+    path.extend(arith_redex(inner));
+    path
+}
+
+fn untyped_redex(t: &untyped::core::Term) -> Vec<usize> {
+    use untyped::core::Term;
+
+    let is_val = |t: &Term| matches!(t, Term::TmAbs(_, _));
+    let (idx, inner) = match t {
+        Term::TmApp(t1, t2) if is_val(t2) => match **t1 {
+            Term::TmAbs(_, _) => return vec![],
+            _ => (0, t1),
+        },
+        Term::TmApp(t1, t2) if is_val(t1) => (1, t2),
+        Term::TmApp(t1, _) => (0, t1),
+        _ => return vec![],
+    };
+    let mut path = vec![idx];
+    path.extend(untyped_redex(inner));
+    path
+}
+
+fn simplebool_redex(t: &simplebool::core::Term) -> Vec<usize> {
+    use simplebool::core::Term;
+
+    let is_val = |t: &Term| matches!(t, Term::TmAbs(_, _, _) | Term::TmTrue | Term::TmFalse);
+    let (idx, inner) = match t {
+        Term::TmApp(t1, t2) if is_val(t1) && is_val(t2) => return vec![],
+        Term::TmApp(t1, t2) if is_val(t1) => (1, t2),
+        Term::TmApp(t1, _) => (0, t1),
+        Term::TmIf(t1, _, _) if matches!(**t1, Term::TmTrue | Term::TmFalse) => return vec![],
+        Term::TmIf(t1, _, _) => (0, t1),
+        _ => return vec![],
+    };
+    let mut path = vec![idx];
+    path.extend(simplebool_redex(inner));
+    path
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Writes the nodes and edges of `tree`, filling in the subtree at `redex`,
+// and returns the id of its root.
+fn write_tree(out: &mut String, indent: &str, prefix: &str, next: &mut usize, tree: &Tree, redex: Option<&[usize]>) -> String {
+    let id = format!("{}n{}", prefix, next);
+    *next += 1;
+    let style = if redex.is_some_and(|path| path.is_empty()) {
+        format!(", style=filled, fillcolor=\"{}\"", REDEX_COLOR)
+    } else {
+        String::new()
+    };
+    writeln!(out, "{}{} [label=\"{}\"{}];", indent, id, escape(&tree.label), style).unwrap();
+
+    for (i, child) in tree.children.iter().enumerate() {
+        // Everything below the redex is part of it.
+        let redex = match redex {
+            Some([]) => Some(&[][..]),
+            Some([j, rest @ ..]) if *j == i => Some(rest),
+            _ => None,
+        };
+        let child_id = write_tree(out, indent, prefix, next, child, redex);
+        writeln!(out, "{}{} -> {};", indent, id, child_id).unwrap();
+    }
+    id
+}
+
+const HEADER: &str = "  node [shape=box, fontname=\"monospace\"];\n";
+
+/// Renders one syntax tree as a DOT graph.
+pub fn graph(tree: &Tree) -> String {
+    let mut out = String::from("digraph term {\n");
+    out.push_str(HEADER);
+    write_tree(&mut out, "  ", "", &mut 0, tree, None);
+    out.push_str("}\n");
+    out
+}
+
+/// Renders a reduction sequence as a row of syntax trees, one cluster per
+/// step, with the redex each step contracts filled in.
+pub fn chain(steps: &[(Tree, Option<Vec<usize>>)]) -> String {
+    let mut out = String::from("digraph reduction {\n");
+    out.push_str(HEADER);
+    let mut roots = vec![];
+    for (i, (tree, redex)) in steps.iter().enumerate() {
+        writeln!(out, "  subgraph cluster_{} {{", i).unwrap();
+        writeln!(out, "    label=\"step {}\";", i).unwrap();
+        roots.push(write_tree(&mut out, "    ", &format!("s{}", i), &mut 0, tree, redex.as_deref()));
+        out.push_str("  }\n");
+    }
+    for pair in roots.windows(2) {
+        writeln!(out, "  {} -> {} [style=dashed, label=\"eval1\"];", pair[0], pair[1]).unwrap();
+    }
+    if roots.len() > 1 {
+        writeln!(out, "  {{ rank=same; {}; }}", roots.join("; ")).unwrap();
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Pairs every term of the reduction sequence starting at `term` with the
+// path to the redex `eval1` contracts in it; the last term has none.
+fn reduction<T, S, R, D>(term: T, step: S, redex: R, tree: D) -> Vec<(Tree, Option<Vec<usize>>)>
+where
+    S: Fn(&T) -> Option<T>,
+    R: Fn(&T) -> Vec<usize>,
+    D: Fn(&T) -> Tree,
+{
+    let mut term = term;
+    let mut out = vec![];
+    for _ in 0..MAX_STEPS {
+        match guard(|| Ok(step(&term))).ok().flatten() {
+            Some(next) => {
+                out.push((tree(&term), Some(redex(&term))));
+                term = next;
+            }
+            None => break,
+        }
+    }
+    out.push((tree(&term), None));
+    out
+}
+
+/// Implements `tapl dot`: one DOT graph per command of `source`, either its
+/// syntax tree or, with `steps`, its whole `eval1` reduction sequence.
+pub fn export(lang: Lang, source: &str, steps: bool) -> Result<Vec<String>, String> {
+    let render = |reduction: Vec<(Tree, Option<Vec<usize>>)>| if steps {
+        chain(&reduction)
+    } else {
+        graph(&reduction[0].0)
+    };
+
+    match lang {
+        Lang::Arith => {
+            let terms = guard(|| {
+                let mut lex = arith::parser::Lexer::new(source.chars());
+                lex.lex_input();
+                Ok(arith::parser::Parser::new(lex.result.into_iter()).parse_commands())
+            })?;
+            Ok(terms.into_iter().map(|t| render(if steps {
+                reduction(t, |t| arith::arith::eval1(t.clone()).ok(), arith_redex, arith_tree)
+            } else {
+                vec![(arith_tree(&t), None)]
+            })).collect())
+        }
+        Lang::Untyped => {
+            let ctx = untyped::core::Context::new();
+            let terms = guard(|| {
+                let mut lex = untyped::parser::Lexer::new(source.chars());
+                lex.lex_input();
+                Ok(untyped::parser::Parser::new(lex.result.into_iter()).parse_commands())
+            })?;
+            Ok(terms.into_iter().map(|t| render(if steps {
+                reduction(t, |t| untyped::core::eval1(t, &ctx).ok(), untyped_redex, |t| untyped_tree(t, &ctx))
+            } else {
+                vec![(untyped_tree(&t, &ctx), None)]
+            })).collect())
+        }
+        Lang::SimpleBool => {
+            let ctx = simplebool::core::Context::new();
+            let terms = guard(|| {
+                let mut lex = simplebool::parser::Lexer::new(source.chars());
+                lex.lex_input();
+                Ok(simplebool::parser::Parser::new(lex.result.into_iter()).parse_commands())
+            })?;
+            Ok(terms.into_iter().map(|t| render(if steps {
+                reduction(t, |t| simplebool::core::eval1(t, &ctx).ok(), simplebool_redex, |t| simplebool_tree(t, &ctx))
+            } else {
+                vec![(simplebool_tree(&t, &ctx), None)]
+            })).collect())
+        }
+        _ => Err(format!("dot export supports arith, untyped and simplebool, not {}", lang)),
+    }
+}
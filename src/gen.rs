@@ -0,0 +1,21 @@
+use tyarith::generator::Generator;
+use tyarith::tyarith::Type;
+use crate::lang::Lang;
+
+/// Implements `tapl gen`: a uniformly chosen well-typed term of type `ty`
+/// and size at most `size`, printed as concrete syntax.
+pub fn generate(lang: Lang, ty: &str, size: usize, seed: u64) -> Result<String, String> {
+    if lang != Lang::TyArith {
+        return Err(format!("gen supports tyarith, not {}", lang));
+    }
+    let ty = match ty {
+        "Nat" => Type::Nat,
+        "Bool" => Type::Bool,
+        _ => return Err(format!("unknown type `{}`, expected Nat or Bool", ty)),
+    };
+    // The number of terms of size 70 no longer fits in a u128.
+    if size == 0 || size > 60 {
+        return Err(format!("size must be between 1 and 60, not {}", size));
+    }
+    Ok(Generator::new(seed).term(ty, size).to_string())
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use crate::lang::Lang;
+use crate::run::{self, Format};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    Passed,
+    Blessed,
+    Failed(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub path: PathBuf,
+    pub status: Status,
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "f") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    for i in 0..expected.len().max(actual.len()) {
+        let e = expected.get(i).copied();
+        let a = actual.get(i).copied();
+        if e != a {
+            return format!(
+                "line {}: expected `{}`, got `{}`",
+                i + 1,
+                e.unwrap_or("<end of output>"),
+                a.unwrap_or("<end of output>"),
+            );
+        }
+    }
+    String::from("outputs differ")
+}
+
+/// Runs the program in `path` and compares its output with the sibling
+/// `.expected` file, or overwrites that file when `bless` is set.
+pub fn check_file(lang: Lang, path: &Path, bless: bool) -> Status {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => return Status::Failed(format!("cannot read: {}", err)),
+    };
+    let (lines, _) = run::output(lang, &source, Format::Text);
+    let mut actual = lines.join("\n");
+    actual.push('\n');
+
+    let expected_path = path.with_extension("expected");
+    if bless {
+        return match fs::write(&expected_path, &actual) {
+            Ok(()) => Status::Blessed,
+            Err(err) => Status::Failed(format!("cannot write {}: {}", expected_path.display(), err)),
+        };
+    }
+    match fs::read_to_string(&expected_path) {
+        Ok(ref expected) if *expected == actual => Status::Passed,
+        Ok(expected) => Status::Failed(diff(&expected, &actual)),
+        Err(err) => Status::Failed(format!("cannot read {}: {}", expected_path.display(), err)),
+    }
+}
+
+/// Checks every `*.f` file under `dir`, spreading the files over one
+/// thread per available core.
+pub fn check_dir(lang: Lang, dir: &Path, bless: bool) -> io::Result<Vec<Report>> {
+    let mut files = vec![];
+    collect(dir, &mut files)?;
+    files.sort();
+
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(files.len());
+    let next = AtomicUsize::new(0);
+    let reports = Mutex::new(vec![]);
+    thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::SeqCst);
+                let path = match files.get(idx) {
+                    Some(path) => path,
+                    None => break,
+                };
+                let status = check_file(lang, path, bless);
+                reports.lock().unwrap().push(Report { path: path.to_owned(), status });
+            });
+        }
+    });
+
+    let mut reports = reports.into_inner().unwrap();
+    reports.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(reports)
+}
+
+/// Implements `tapl test`: prints one line per file and a summary, and
+/// returns whether every file passed.
+pub fn test_dir(lang: Lang, dir: &str, bless: bool) -> bool {
+    let reports = match check_dir(lang, Path::new(dir), bless) {
+        Ok(reports) => reports,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", dir, err);
+            return false;
+        }
+    };
+
+    let (mut passed, mut blessed, mut failed) = (0, 0, 0);
+    for report in reports.iter() {
+        match report.status {
+            Status::Passed => {
+                passed += 1;
+                println!("ok      {}", report.path.display());
+            }
+            Status::Blessed => {
+                blessed += 1;
+                println!("blessed {}", report.path.display());
+            }
+            Status::Failed(ref why) => {
+                failed += 1;
+                println!("FAILED  {}: {}", report.path.display(), why);
+            }
+        }
+    }
+    println!("\n{} passed; {} failed; {} blessed", passed, failed, blessed);
+    failed == 0
+}
@@ -0,0 +1,398 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn str(s: &str) -> Json {
+        Json::Str(s.to_string())
+    }
+
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Json, String> {
+        let mut reader = Reader { chars: input.chars().collect(), pos: 0 };
+        let value = reader.value()?;
+        reader.skip_ws();
+        if reader.pos < reader.chars.len() {
+            return Err(format!("trailing characters at {}", reader.pos));
+        }
+        Ok(value)
+    }
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}` at {}", c, self.pos))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for c in word.chars() {
+            self.expect(c)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::Str(self.string()?)),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.skip_ws();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(format!("expected `,` or `]` at {}", self.pos)),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut fields = vec![];
+                self.skip_ws();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_ws();
+                    let key = self.string()?;
+                    self.skip_ws();
+                    self.expect(':')?;
+                    fields.push((key, self.value()?));
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some('}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(format!("expected `,` or `}}` at {}", self.pos)),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_digit() || "+-.eE".contains(c) {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                match text.parse::<i64>() {
+                    Ok(n) => Ok(Json::Number(n)),
+                    Err(_) => text.parse::<f64>()
+                        .map(|n| Json::Number(n as i64))
+                        .map_err(|_| format!("bad number `{}`", text)),
+                }
+            }
+            _ => Err(format!("unexpected input at {}", self.pos)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(String::from("unterminated string")),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(result);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let c = self.peek().ok_or("unterminated string")?;
+                    self.pos += 1;
+                    match c {
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        'b' => result.push('\u{8}'),
+                        'f' => result.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&code) && self.peek() == Some('\\') {
+                                self.pos += 1;
+                                self.expect('u')?;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(String::from("bad surrogate pair"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            result.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        c => result.push(c),
+                    }
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    result.push(c);
+                }
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        if self.pos + 4 > self.chars.len() {
+            return Err(String::from("bad unicode escape"));
+        }
+        let text: String = self.chars[self.pos..self.pos + 4].iter().collect();
+        self.pos += 4;
+        u32::from_str_radix(&text, 16).map_err(|_| format!("bad unicode escape `{}`", text))
+    }
+}
+
+fn write_str(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::Str(s) => write_str(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_str(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn node(kind: &str, mut fields: Vec<(&str, Json)>) -> Json {
+    fields.insert(0, ("kind", Json::str(kind)));
+    Json::object(fields)
+}
+
+pub fn arith_term(t: &arith::arith::Term) -> Json {
+    use arith::arith::Term;
+    match t {
+        Term::True => node("True", vec![]),
+        Term::False => node("False", vec![]),
+        Term::Zero => node("Zero", vec![]),
+        Term::IsZero(t1) => node("IsZero", vec![("term", arith_term(t1))]),
+        Term::Succ(t1) => node("Succ", vec![("term", arith_term(t1))]),
+        Term::Pred(t1) => node("Pred", vec![("term", arith_term(t1))]),
+        Term::If(t1, t2, t3) => node("If", vec![
+            ("cond", arith_term(t1)),
+            ("then", arith_term(t2)),
+            ("else", t3.as_ref().map_or(Json::Null, |t3| arith_term(t3))),
+        ]),
+    }
+}
+
+pub fn tyarith_term(t: &tyarith::tyarith::Term) -> Json {
+    use tyarith::tyarith::Term;
+    match t {
+        Term::True => node("True", vec![]),
+        Term::False => node("False", vec![]),
+        Term::Zero => node("Zero", vec![]),
+        Term::IsZero(t1) => node("IsZero", vec![("term", tyarith_term(t1))]),
+        Term::Succ(t1) => node("Succ", vec![("term", tyarith_term(t1))]),
+        Term::Pred(t1) => node("Pred", vec![("term", tyarith_term(t1))]),
+        Term::Num(n) => node("Num", vec![("value", i64::try_from(*n).map_or_else(|_| Json::Str(n.to_string()), Json::Number))]),
+        Term::Prim(prim, t1, t2) => node("Prim", vec![
+            ("op", Json::Str(prim.to_string())),
+            ("left", tyarith_term(t1)),
+            ("right", tyarith_term(t2)),
+        ]),
+        Term::If(t1, t2, t3) => node("If", vec![
+            ("cond", tyarith_term(t1)),
+            ("then", tyarith_term(t2)),
+            ("else", t3.as_ref().map_or(Json::Null, |t3| tyarith_term(t3))),
+        ]),
+    }
+}
+
+pub fn tyarith_type(ty: &tyarith::tyarith::Type) -> Json {
+    node(&format!("{:?}", ty), vec![])
+}
+
+pub fn untyped_term(t: &untyped::core::Term, ctx: &untyped::core::Context) -> Json {
+    use untyped::core::Term;
+    match t {
+        Term::TmVar(idx, n) => node("TmVar", vec![
+            ("index", Json::Number(*idx as i64)),
+            ("ctxlen", Json::Number(*n as i64)),
+            ("name", ctx.index_to_name(*idx).map_or(Json::Null, Json::Str)),
+        ]),
+        Term::TmAbs(x, t1) => {
+            let (ctx1, name) = ctx.pick_fresh_name(x);
+            node("TmAbs", vec![("name", Json::Str(name)), ("body", untyped_term(t1, &ctx1))])
+        }
+        Term::TmApp(t1, t2) => node("TmApp", vec![
+            ("fun", untyped_term(t1, ctx)),
+            ("arg", untyped_term(t2, ctx)),
+        ]),
+    }
+}
+
+pub fn fulluntyped_term(t: &fulluntyped::syntax::Term, ctx: &fulluntyped::syntax::Context) -> Json {
+    use fulluntyped::syntax::Term;
+    match t {
+        Term::True => node("True", vec![]),
+        Term::False => node("False", vec![]),
+        Term::Zero => node("Zero", vec![]),
+        Term::Number(n) => node("Number", vec![("value", Json::Number(*n as i64))]),
+        Term::IsZero(t1) => node("IsZero", vec![("term", fulluntyped_term(t1, ctx))]),
+        Term::Succ(t1) => node("Succ", vec![("term", fulluntyped_term(t1, ctx))]),
+        Term::Pred(t1) => node("Pred", vec![("term", fulluntyped_term(t1, ctx))]),
+        Term::If(t1, t2, t3) => node("If", vec![
+            ("cond", fulluntyped_term(t1, ctx)),
+            ("then", fulluntyped_term(t2, ctx)),
+            ("else", t3.as_ref().map_or(Json::Null, |t3| fulluntyped_term(t3, ctx))),
+        ]),
+        Term::TmVar(idx, n) => node("TmVar", vec![
+            ("index", Json::Number(*idx as i64)),
+            ("ctxlen", Json::Number(*n as i64)),
+            ("name", ctx.index_to_name(*idx).map_or(Json::Null, Json::Str)),
+        ]),
+        Term::TmAbs(x, t1) => {
+            let (ctx1, name) = ctx.pick_fresh_name(x);
+            node("TmAbs", vec![("name", Json::Str(name)), ("body", fulluntyped_term(t1, &ctx1))])
+        }
+        Term::TmApp(t1, t2) => node("TmApp", vec![
+            ("fun", fulluntyped_term(t1, ctx)),
+            ("arg", fulluntyped_term(t2, ctx)),
+        ]),
+    }
+}
+
+pub fn simplebool_type(ty: &simplebool::core::Type) -> Json {
+    use simplebool::core::Type;
+    match ty {
+        Type::TyBool => node("TyBool", vec![]),
+        Type::TyArr(ty1, ty2) => node("TyArr", vec![("from", simplebool_type(ty1)), ("to", simplebool_type(ty2))]),
+    }
+}
+
+pub fn simplebool_term(t: &simplebool::core::Term, ctx: &simplebool::core::Context) -> Json {
+    use simplebool::core::Term;
+    match t {
+        Term::TmTrue => node("TmTrue", vec![]),
+        Term::TmFalse => node("TmFalse", vec![]),
+        Term::TmIf(t1, t2, t3) => node("TmIf", vec![
+            ("cond", simplebool_term(t1, ctx)),
+            ("then", simplebool_term(t2, ctx)),
+            ("else", simplebool_term(t3, ctx)),
+        ]),
+        Term::TmVar(idx, n) => node("TmVar", vec![
+            ("index", Json::Number(*idx as i64)),
+            ("ctxlen", Json::Number(*n as i64)),
+            ("name", ctx.index_to_name(*idx).map_or(Json::Null, Json::Str)),
+        ]),
+        Term::TmAbs(x, ty, t1) => {
+            let (ctx1, name) = ctx.pick_fresh_name(x, ty.clone());
+            node("TmAbs", vec![
+                ("name", Json::Str(name)),
+                ("type", simplebool_type(ty)),
+                ("body", simplebool_term(t1, &ctx1)),
+            ])
+        }
+        Term::TmApp(t1, t2) => node("TmApp", vec![
+            ("fun", simplebool_term(t1, ctx)),
+            ("arg", simplebool_term(t2, ctx)),
+        ]),
+    }
+}
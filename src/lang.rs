@@ -0,0 +1,524 @@
+use std::cell::Cell;
+use std::fmt;
+use std::panic;
+use std::str::FromStr;
+use crate::json::{self, Json};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Lang {
+    Arith,
+    TyArith,
+    Untyped,
+    FullUntyped,
+    SimpleBool,
+}
+
+pub const LANGS: [Lang; 5] = [Lang::Arith, Lang::TyArith, Lang::Untyped, Lang::FullUntyped, Lang::SimpleBool];
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "arith" => Ok(Lang::Arith),
+            "tyarith" => Ok(Lang::TyArith),
+            "untyped" => Ok(Lang::Untyped),
+            "fulluntyped" => Ok(Lang::FullUntyped),
+            "simplebool" => Ok(Lang::SimpleBool),
+            _ => Err(format!("unknown language `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Lang::Arith => "arith",
+            Lang::TyArith => "tyarith",
+            Lang::Untyped => "untyped",
+            Lang::FullUntyped => "fulluntyped",
+            Lang::SimpleBool => "simplebool",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub ty: Option<String>,
+    pub value: String,
+    pub ast: Ast,
+}
+
+/// The parsed term, its type and its value as JSON syntax trees.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ast {
+    pub term: Json,
+    pub ty: Option<Json>,
+    pub value: Json,
+}
+
+impl Outcome {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("term", self.ast.term.clone()),
+            ("type", self.ast.ty.clone().unwrap_or(Json::Null)),
+            ("value", self.ast.value.clone()),
+            ("text", Json::Str(self.to_string())),
+        ])
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.ty {
+            Some(ref ty) => write!(f, "{} : {}", self.value, ty),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+thread_local! {
+    static GUARDED: Cell<bool> = const { Cell::new(false) };
+}
+
+// The language crates report bad input by panicking, so every call into
+// them goes through here. The hook keeps those panics off stderr.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !GUARDED.with(|g| g.get()) {
+            default_hook(info);
+        }
+    }));
+}
+
+pub fn guard<T, F: FnOnce() -> Result<T, String>>(f: F) -> Result<T, String> {
+    let prev = GUARDED.with(|g| g.replace(true));
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    GUARDED.with(|g| g.set(prev));
+
+    match result {
+        Ok(out) => out,
+        Err(payload) => {
+            // The crates end their panic messages with `~~`.
+            if let Some(msg) = payload.downcast_ref::<&str>() {
+                Err(msg.trim_end_matches('~').to_string())
+            } else if let Some(msg) = payload.downcast_ref::<String>() {
+                Err(msg.trim_end_matches('~').to_string())
+            } else {
+                Err(String::from("parse error"))
+            }
+        }
+    }
+}
+
+/// The number of small steps an untyped or fulluntyped command may take
+/// before `Session::run` reports it as diverging.
+pub const STEP_LIMIT: usize = 1_000;
+
+fn no_term() -> String {
+    String::from("no term to parse")
+}
+
+/// A term or type printed in the language's concrete syntax, together with
+/// its JSON syntax tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shown {
+    pub text: String,
+    pub ast: Json,
+}
+
+// What the session needs from each language crate. `Ctx` is the naming
+// context terms are parsed and printed in; `()` for languages without
+// variables.
+trait Language {
+    type Term: Clone;
+    type Ctx;
+    // Whether evaluation can diverge, so `run` bounds it by `STEP_LIMIT`.
+    const DIVERGES: bool = false;
+
+    fn parse_commands(input: &str, ctx: &Self::Ctx) -> Vec<Self::Term>;
+    fn show(term: &Self::Term, ctx: &Self::Ctx) -> Shown;
+    // `Ok(None)` when the language has no type system.
+    fn type_of(term: &Self::Term, ctx: &Self::Ctx) -> Result<Option<Shown>, String>;
+    fn eval(term: &Self::Term, ctx: &Self::Ctx) -> Result<Self::Term, String>;
+    fn eval1(term: &Self::Term, ctx: &Self::Ctx) -> Option<Self::Term>;
+}
+
+struct ArithLang;
+
+impl Language for ArithLang {
+    type Term = arith::arith::Term;
+    type Ctx = ();
+
+    fn parse_commands(input: &str, _ctx: &()) -> Vec<Self::Term> {
+        let mut lex = arith::parser::Lexer::new(input.chars());
+        lex.lex_input();
+        arith::parser::Parser::new(lex.result.into_iter()).parse_commands()
+    }
+
+    fn show(term: &Self::Term, _ctx: &()) -> Shown {
+        Shown { text: term.to_string(), ast: json::arith_term(term) }
+    }
+
+    fn type_of(_term: &Self::Term, _ctx: &()) -> Result<Option<Shown>, String> {
+        Ok(None)
+    }
+
+    fn eval(term: &Self::Term, _ctx: &()) -> Result<Self::Term, String> {
+        use arith::arith::Classification;
+
+        match arith::arith::classify(term.clone()) {
+            Classification::Numeric(value) | Classification::Boolean(value) => Ok(value),
+            Classification::Stuck { subterm, reason, .. } => Err(format!("stuck at `{}`: {}", subterm, reason)),
+        }
+    }
+
+    fn eval1(term: &Self::Term, _ctx: &()) -> Option<Self::Term> {
+        arith::arith::eval1(term.clone()).ok()
+    }
+}
+
+struct TyArithLang;
+
+impl Language for TyArithLang {
+    type Term = tyarith::tyarith::Term;
+    type Ctx = ();
+
+    fn parse_commands(input: &str, _ctx: &()) -> Vec<Self::Term> {
+        let mut lex = tyarith::parser::Lexer::new(input.chars());
+        lex.lex_input();
+        tyarith::parser::Parser::new(lex.result.into_iter()).parse_commands()
+    }
+
+    fn show(term: &Self::Term, _ctx: &()) -> Shown {
+        Shown { text: term.to_string(), ast: json::tyarith_term(term) }
+    }
+
+    fn type_of(term: &Self::Term, _ctx: &()) -> Result<Option<Shown>, String> {
+        match tyarith::derivation::derive(term) {
+            Ok(d) => Ok(Some(Shown { text: d.ty.to_string(), ast: json::tyarith_type(&d.ty) })),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    fn eval(term: &Self::Term, _ctx: &()) -> Result<Self::Term, String> {
+        Ok(tyarith::tyarith::eval(term.clone()))
+    }
+
+    fn eval1(term: &Self::Term, _ctx: &()) -> Option<Self::Term> {
+        tyarith::tyarith::eval1(term.clone()).ok()
+    }
+}
+
+struct UntypedLang;
+
+impl Language for UntypedLang {
+    type Term = untyped::core::Term;
+    type Ctx = untyped::core::Context;
+    const DIVERGES: bool = true;
+
+    fn parse_commands(input: &str, ctx: &Self::Ctx) -> Vec<Self::Term> {
+        let mut lex = untyped::parser::Lexer::new(input.chars());
+        lex.lex_input();
+        let mut parser = untyped::parser::Parser::new(lex.result.into_iter());
+        parser.ctx = ctx.clone();
+        parser.parse_commands()
+    }
+
+    fn show(term: &Self::Term, ctx: &Self::Ctx) -> Shown {
+        Shown { text: untyped::core::ContextTerm::new(ctx, term).to_string(), ast: json::untyped_term(term, ctx) }
+    }
+
+    fn type_of(_term: &Self::Term, _ctx: &Self::Ctx) -> Result<Option<Shown>, String> {
+        Ok(None)
+    }
+
+    fn eval(term: &Self::Term, ctx: &Self::Ctx) -> Result<Self::Term, String> {
+        untyped::core::eval(term, ctx).map_err(|err| format!("{:?}", err))
+    }
+
+    fn eval1(term: &Self::Term, ctx: &Self::Ctx) -> Option<Self::Term> {
+        untyped::core::eval1(term, ctx).ok()
+    }
+}
+
+struct FullUntypedLang;
+
+impl Language for FullUntypedLang {
+    type Term = fulluntyped::syntax::Term;
+    type Ctx = fulluntyped::syntax::Context;
+    const DIVERGES: bool = true;
+
+    fn parse_commands(input: &str, ctx: &Self::Ctx) -> Vec<Self::Term> {
+        let mut lex = fulluntyped::lexer::Lexer::new(input.chars());
+        lex.lex_input();
+        let mut parser = fulluntyped::parser::Parser::new(lex.result.into_iter());
+        parser.ctx = ctx.clone();
+        parser.parse_commands()
+    }
+
+    fn show(term: &Self::Term, ctx: &Self::Ctx) -> Shown {
+        Shown { text: format!("{:?}", term), ast: json::fulluntyped_term(term, ctx) }
+    }
+
+    fn type_of(_term: &Self::Term, _ctx: &Self::Ctx) -> Result<Option<Shown>, String> {
+        Ok(None)
+    }
+
+    fn eval(term: &Self::Term, ctx: &Self::Ctx) -> Result<Self::Term, String> {
+        fulluntyped::core::eval(term, ctx).map_err(|err| format!("{:?}", err))
+    }
+
+    fn eval1(term: &Self::Term, ctx: &Self::Ctx) -> Option<Self::Term> {
+        fulluntyped::core::eval1(term, ctx).ok()
+    }
+}
+
+struct SimpleBoolLang;
+
+impl Language for SimpleBoolLang {
+    type Term = simplebool::core::Term;
+    type Ctx = simplebool::core::Context;
+
+    fn parse_commands(input: &str, ctx: &Self::Ctx) -> Vec<Self::Term> {
+        let mut lex = simplebool::parser::Lexer::new(input.chars());
+        lex.lex_input();
+        let mut parser = simplebool::parser::Parser::new(lex.result.into_iter());
+        parser.ctx = ctx.clone();
+        parser.parse_commands()
+    }
+
+    fn show(term: &Self::Term, ctx: &Self::Ctx) -> Shown {
+        Shown { text: simplebool::core::ContextTerm::new(ctx, term).to_string(), ast: json::simplebool_term(term, ctx) }
+    }
+
+    fn type_of(term: &Self::Term, ctx: &Self::Ctx) -> Result<Option<Shown>, String> {
+        match simplebool::core::type_of(term, ctx) {
+            Ok(ty) => Ok(Some(Shown { text: ty.to_string(), ast: json::simplebool_type(&ty) })),
+            Err(err) => Err(format!("{:?}", err)),
+        }
+    }
+
+    fn eval(term: &Self::Term, ctx: &Self::Ctx) -> Result<Self::Term, String> {
+        simplebool::core::eval(term, ctx).map_err(|err| format!("{:?}", err))
+    }
+
+    fn eval1(term: &Self::Term, ctx: &Self::Ctx) -> Option<Self::Term> {
+        simplebool::core::eval1(term, ctx).ok()
+    }
+}
+
+fn parse<L: Language>(input: &str, ctx: &L::Ctx) -> Result<Vec<Shown>, String> {
+    let terms = guard(|| Ok(L::parse_commands(input, ctx)))?;
+    Ok(terms.iter().map(|t| L::show(t, ctx)).collect())
+}
+
+fn types<L: Language>(input: &str, ctx: &L::Ctx) -> Result<Vec<Result<Shown, String>>, String> {
+    let terms = guard(|| Ok(L::parse_commands(input, ctx)))?;
+    Ok(terms.iter().map(|t| guard(|| L::type_of(t, ctx))?.ok_or_else(|| String::from("no type system"))).collect())
+}
+
+// Takes small steps from `term` to its normal form, giving up after
+// `STEP_LIMIT` of them.
+fn normalize<L: Language>(term: &L::Term, ctx: &L::Ctx) -> Result<L::Term, String> {
+    let mut term = term.clone();
+    for _ in 0..STEP_LIMIT {
+        match L::eval1(&term, ctx) {
+            Some(next) => term = next,
+            None => return Ok(term),
+        }
+    }
+    Err(String::from("step limit reached"))
+}
+
+fn run<L: Language>(input: &str, ctx: &L::Ctx) -> Result<Vec<Result<Outcome, String>>, String> {
+    let terms = guard(|| Ok(L::parse_commands(input, ctx)))?;
+    Ok(terms.iter().map(|t| guard(|| {
+        let ty = L::type_of(t, ctx)?;
+        let value = if L::DIVERGES { L::eval(&normalize::<L>(t, ctx)?, ctx)? } else { L::eval(t, ctx)? };
+        let (term, value) = (L::show(t, ctx), L::show(&value, ctx));
+        Ok(Outcome {
+            ty: ty.as_ref().map(|ty| ty.text.clone()),
+            value: value.text,
+            ast: Ast { term: term.ast, ty: ty.map(|ty| ty.ast), value: value.ast },
+        })
+    })).collect())
+}
+
+fn steps<L: Language>(input: &str, ctx: &L::Ctx, limit: usize) -> Result<(Vec<Shown>, bool), String> {
+    guard(|| {
+        let mut term = L::parse_commands(input, ctx).into_iter().next().ok_or_else(no_term)?;
+        let mut out = vec![L::show(&term, ctx)];
+        for _ in 0..limit {
+            match L::eval1(&term, ctx) {
+                Some(next) => {
+                    out.push(L::show(&next, ctx));
+                    term = next;
+                }
+                None => return Ok((out, true)),
+            }
+        }
+        Ok((out, L::eval1(&term, ctx).is_none()))
+    })
+}
+
+// TAPL's simplebool type syntax: `Bool` and right-associative `->`.
+fn parse_simplebool_type(input: &str) -> Result<simplebool::core::Type, String> {
+    use simplebool::core::Type;
+
+    fn arrow(tokens: &[String], pos: &mut usize) -> Result<Type, String> {
+        let lhs = match tokens.get(*pos).map(|t| t.as_str()) {
+            Some("Bool") => {
+                *pos += 1;
+                Type::TyBool
+            }
+            Some("(") => {
+                *pos += 1;
+                let ty = arrow(tokens, pos)?;
+                if tokens.get(*pos).map(|t| t.as_str()) != Some(")") {
+                    return Err(String::from("expected `)` in type"));
+                }
+                *pos += 1;
+                ty
+            }
+            _ => return Err(String::from("expected a type")),
+        };
+        if tokens.get(*pos).map(|t| t.as_str()) == Some("->") {
+            *pos += 1;
+            return Ok(Type::TyArr(Box::new(lhs), Box::new(arrow(tokens, pos)?)));
+        }
+        Ok(lhs)
+    }
+
+    let tokens: Vec<String> = input.replace('(', " ( ").replace(')', " ) ").replace("->", " -> ")
+        .split_whitespace().map(|t| t.to_string()).collect();
+    let mut pos = 0;
+    let ty = arrow(&tokens, &mut pos)?;
+    if pos != tokens.len() {
+        return Err(format!("unexpected `{}` in type", tokens[pos]));
+    }
+    Ok(ty)
+}
+
+enum Ctx {
+    Arith,
+    TyArith,
+    Untyped(untyped::core::Context),
+    FullUntyped(fulluntyped::syntax::Context),
+    SimpleBool(simplebool::core::Context),
+}
+
+/// A language together with the free variables bound so far with `bind`.
+/// Terms are parsed and printed in that context, so later commands can
+/// refer to those variables; a session never binds a name to a term.
+pub struct Session {
+    pub lang: Lang,
+    ctx: Ctx,
+}
+
+impl Session {
+    pub fn new(lang: Lang) -> Self {
+        let ctx = match lang {
+            Lang::Arith => Ctx::Arith,
+            Lang::TyArith => Ctx::TyArith,
+            Lang::Untyped => Ctx::Untyped(untyped::core::Context::new()),
+            Lang::FullUntyped => Ctx::FullUntyped(fulluntyped::syntax::Context::new()),
+            Lang::SimpleBool => Ctx::SimpleBool(simplebool::core::Context::new()),
+        };
+        Session { lang, ctx }
+    }
+
+    /// Binds a free variable, as TAPL's `x/;` (untyped languages) and
+    /// `x : T;` (simplebool) commands do. Returns the name actually bound,
+    /// which is primed if `name` is already taken.
+    pub fn bind(&mut self, name: &str, ty: Option<&str>) -> Result<String, String> {
+        let (ctx, name) = match self.ctx {
+            Ctx::Untyped(ref ctx) => {
+                let (ctx, name) = ctx.pick_fresh_name(name);
+                (Ctx::Untyped(ctx), name)
+            }
+            Ctx::FullUntyped(ref ctx) => {
+                let (ctx, name) = ctx.pick_fresh_name(name);
+                (Ctx::FullUntyped(ctx), name)
+            }
+            Ctx::SimpleBool(ref ctx) => {
+                let ty = parse_simplebool_type(ty.ok_or_else(|| String::from("simplebool bindings need a type"))?)?;
+                let (ctx, name) = ctx.pick_fresh_name(name, ty);
+                (Ctx::SimpleBool(ctx), name)
+            }
+            Ctx::Arith | Ctx::TyArith => return Err(format!("{} has no variables", self.lang)),
+        };
+        self.ctx = ctx;
+        Ok(name)
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Vec<Shown>, String> {
+        match self.ctx {
+            Ctx::Arith => parse::<ArithLang>(input, &()),
+            Ctx::TyArith => parse::<TyArithLang>(input, &()),
+            Ctx::Untyped(ref ctx) => parse::<UntypedLang>(input, ctx),
+            Ctx::FullUntyped(ref ctx) => parse::<FullUntypedLang>(input, ctx),
+            Ctx::SimpleBool(ref ctx) => parse::<SimpleBoolLang>(input, ctx),
+        }
+    }
+
+    /// Type checks every command of `input`.
+    pub fn type_of(&self, input: &str) -> Result<Vec<Result<Shown, String>>, String> {
+        match self.ctx {
+            Ctx::TyArith => types::<TyArithLang>(input, &()),
+            Ctx::SimpleBool(ref ctx) => types::<SimpleBoolLang>(input, ctx),
+            _ => Err(format!("{} has no type system", self.lang)),
+        }
+    }
+
+    /// Parses `input` as a `;`-separated list of commands and runs each of
+    /// them: type check where the language has a type system, then evaluate
+    /// to a normal form, in at most `STEP_LIMIT` steps for the untyped
+    /// languages, which need not terminate. Parse errors abort
+    /// the whole input; type and evaluation errors are reported per
+    /// command.
+    pub fn run(&self, input: &str) -> Result<Vec<Result<Outcome, String>>, String> {
+        match self.ctx {
+            Ctx::Arith => run::<ArithLang>(input, &()),
+            Ctx::TyArith => run::<TyArithLang>(input, &()),
+            Ctx::Untyped(ref ctx) => run::<UntypedLang>(input, ctx),
+            Ctx::FullUntyped(ref ctx) => run::<FullUntypedLang>(input, ctx),
+            Ctx::SimpleBool(ref ctx) => run::<SimpleBoolLang>(input, ctx),
+        }
+    }
+
+    /// Returns the first command of `input` followed by at most `limit` of
+    /// its small-step reducts, and whether the last one is a normal form.
+    pub fn steps(&self, input: &str, limit: usize) -> Result<(Vec<Shown>, bool), String> {
+        match self.ctx {
+            Ctx::Arith => steps::<ArithLang>(input, &(), limit),
+            Ctx::TyArith => steps::<TyArithLang>(input, &(), limit),
+            Ctx::Untyped(ref ctx) => steps::<UntypedLang>(input, ctx, limit),
+            Ctx::FullUntyped(ref ctx) => steps::<FullUntypedLang>(input, ctx, limit),
+            Ctx::SimpleBool(ref ctx) => steps::<SimpleBoolLang>(input, ctx, limit),
+        }
+    }
+}
+
+impl Lang {
+    pub fn run(&self, input: &str) -> Result<Vec<Result<Outcome, String>>, String> {
+        Session::new(*self).run(input)
+    }
+
+    pub fn type_of(&self, input: &str) -> Result<String, String> {
+        match Session::new(*self).type_of(input)?.into_iter().next() {
+            Some(ty) => ty.map(|ty| ty.text),
+            None => Err(no_term()),
+        }
+    }
+
+    /// Returns the term followed by every intermediate term of its
+    /// small-step evaluation, up to `STEP_LIMIT` of them, and whether the
+    /// last one is a normal form.
+    pub fn steps(&self, input: &str) -> Result<(Vec<String>, bool), String> {
+        let (steps, normal) = Session::new(*self).steps(input, STEP_LIMIT)?;
+        Ok((steps.into_iter().map(|t| t.text).collect(), normal))
+    }
+}
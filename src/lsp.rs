@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use simplebool::core::{type_of, ContextTerm, Type};
+use simplebool::parser::{Lexer, Node, Parser};
+use crate::json::Json;
+use crate::lang::guard;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub start: usize,
+    pub end: usize,
+    pub message: String,
+}
+
+// A parsed subterm with its character range and the result of type
+// checking it.
+struct Typed {
+    start: usize,
+    end: usize,
+    ty: Result<Type, String>,
+}
+
+/// Everything the server knows about one simplebool document: the errors
+/// to report and the type of every subterm the parser produced.
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    subterms: Vec<Typed>,
+}
+
+impl Analysis {
+    pub fn new(text: &str) -> Analysis {
+        let end = text.chars().count();
+        let mut lex = Lexer::new(text.chars());
+        if let Err(err) = guard(|| {
+            lex.lex_input();
+            Ok(())
+        }) {
+            let start = lex.offset();
+            let diagnostic = Diagnostic { start, end: (start + 1).min(end), message: err };
+            return Analysis { diagnostics: vec![diagnostic], subterms: vec![] };
+        }
+
+        let spans = lex.spans;
+        let mut parser = Parser::new(lex.result.into_iter());
+        if let Err(err) = guard(|| Ok(parser.parse_commands().len())) {
+            let (start, end) = spans.get(parser.position()).copied().unwrap_or((end, end));
+            let diagnostic = Diagnostic { start, end, message: err };
+            return Analysis { diagnostics: vec![diagnostic], subterms: vec![] };
+        }
+
+        let mut subterms: Vec<Typed> = vec![];
+        for Node { start, end, term, ctx } in parser.nodes {
+            // A node can end past the last token when the parser read to
+            // the end of the input.
+            let end = end.min(spans.len());
+            if start >= end {
+                continue;
+            }
+            let (start, end) = (spans[start].0, spans[end - 1].1);
+            if subterms.iter().any(|t| t.start == start && t.end == end) {
+                continue;
+            }
+            let ty = match guard(|| Ok(type_of(&term, &ctx))) {
+                Ok(Ok(ty)) => Ok(ty),
+                Ok(Err(_)) => Err(format!("type error in {}", ContextTerm::new(&ctx, &term))),
+                Err(err) => Err(err),
+            };
+            subterms.push(Typed { start, end, ty });
+        }
+
+        // Only the innermost ill-typed subterms are reported; everything
+        // around them fails for the same reason.
+        let diagnostics = subterms.iter()
+            .filter(|t| t.ty.is_err())
+            .filter(|t| !subterms.iter().any(|u| u.ty.is_err() && t.start <= u.start && u.end <= t.end && (u.end - u.start) < (t.end - t.start)))
+            .map(|t| Diagnostic { start: t.start, end: t.end, message: t.ty.clone().unwrap_err() })
+            .collect();
+
+        Analysis { diagnostics, subterms }
+    }
+
+    /// The type (or type error) of the smallest subterm around `offset`,
+    /// with its range.
+    pub fn hover(&self, offset: usize) -> Option<(usize, usize, String)> {
+        self.subterms.iter()
+            .filter(|t| t.start <= offset && offset < t.end)
+            .min_by_key(|t| t.end - t.start)
+            .map(|t| match t.ty {
+                Ok(ref ty) => (t.start, t.end, ty.to_string()),
+                Err(ref err) => (t.start, t.end, err.clone()),
+            })
+    }
+}
+
+// LSP positions count UTF-16 code units within a line; the lexer counts
+// characters from the start of the text.
+fn to_position(text: &str, offset: usize) -> Json {
+    let (mut line, mut character) = (0, 0);
+    for c in text.chars().take(offset) {
+        if c == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += c.len_utf16();
+        }
+    }
+    Json::object(vec![("line", Json::Number(line)), ("character", Json::Number(character as i64))])
+}
+
+fn to_offset(text: &str, position: &Json) -> Option<usize> {
+    let line = position.get("line")?.as_i64()?;
+    let character = position.get("character")?.as_i64()? as usize;
+    let (mut cur_line, mut cur_char) = (0, 0);
+    for (offset, c) in text.chars().enumerate() {
+        if cur_line == line && (cur_char >= character || c == '\n') {
+            return Some(offset);
+        }
+        if c == '\n' {
+            cur_line += 1;
+            cur_char = 0;
+        } else {
+            cur_char += c.len_utf16();
+        }
+    }
+    Some(text.chars().count())
+}
+
+fn range(text: &str, start: usize, end: usize) -> Json {
+    Json::object(vec![("start", to_position(text, start)), ("end", to_position(text, end))])
+}
+
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = match length {
+        Some(length) => length,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length")),
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body).map(Some).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_message<W: Write>(output: &mut W, message: Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn response(id: Json, result: Json) -> Json {
+    Json::object(vec![("jsonrpc", Json::str("2.0")), ("id", id), ("result", result)])
+}
+
+fn error_response(id: Json, code: i64, message: &str) -> Json {
+    let error = Json::object(vec![("code", Json::Number(code)), ("message", Json::str(message))]);
+    Json::object(vec![("jsonrpc", Json::str("2.0")), ("id", id), ("error", error)])
+}
+
+fn publish_diagnostics(uri: &str, text: &str) -> Json {
+    let diagnostics = Analysis::new(text).diagnostics.into_iter().map(|d| Json::object(vec![
+        ("range", range(text, d.start, d.end)),
+        ("severity", Json::Number(1)),
+        ("source", Json::str("simplebool")),
+        ("message", Json::Str(d.message)),
+    ])).collect();
+    let params = Json::object(vec![("uri", Json::str(uri)), ("diagnostics", Json::Array(diagnostics))]);
+    Json::object(vec![
+        ("jsonrpc", Json::str("2.0")),
+        ("method", Json::str("textDocument/publishDiagnostics")),
+        ("params", params),
+    ])
+}
+
+fn hover(text: &str, position: &Json) -> Json {
+    let offset = match to_offset(text, position) {
+        Some(offset) => offset,
+        None => return Json::Null,
+    };
+    match Analysis::new(text).hover(offset) {
+        Some((start, end, info)) => Json::object(vec![
+            ("contents", Json::object(vec![("kind", Json::str("plaintext")), ("value", Json::Str(info))])),
+            ("range", range(text, start, end)),
+        ]),
+        None => Json::Null,
+    }
+}
+
+/// Runs a language server for simplebool documents, speaking JSON-RPC
+/// with LSP framing over `input` and `output` until the client sends
+/// `exit` or closes the stream.
+pub fn serve<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {
+    let mut documents: HashMap<String, String> = HashMap::new();
+
+    while let Some(body) = read_message(&mut input)? {
+        let message = match Json::parse(&body) {
+            Ok(message) => message,
+            Err(err) => {
+                write_message(&mut output, error_response(Json::Null, -32700, &err))?;
+                continue;
+            }
+        };
+        let id = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let document = params.get("textDocument");
+        let uri = document.and_then(|d| d.get("uri")).and_then(|u| u.as_str()).unwrap_or("").to_string();
+
+        match message.get("method").and_then(|m| m.as_str()).unwrap_or("") {
+            "initialize" => {
+                let capabilities = Json::object(vec![
+                    ("textDocumentSync", Json::Number(1)),
+                    ("hoverProvider", Json::Bool(true)),
+                ]);
+                let server_info = Json::object(vec![("name", Json::str("tapl-simplebool"))]);
+                let result = Json::object(vec![("capabilities", capabilities), ("serverInfo", server_info)]);
+                write_message(&mut output, response(id.unwrap_or(Json::Null), result))?;
+            }
+            "textDocument/didOpen" => {
+                let text = document.and_then(|d| d.get("text")).and_then(|t| t.as_str()).unwrap_or("").to_string();
+                write_message(&mut output, publish_diagnostics(&uri, &text))?;
+                documents.insert(uri, text);
+            }
+            "textDocument/didChange" => {
+                let text = match params.get("contentChanges") {
+                    Some(Json::Array(changes)) => changes.last().and_then(|c| c.get("text")).and_then(|t| t.as_str()),
+                    _ => None,
+                };
+                if let Some(text) = text {
+                    write_message(&mut output, publish_diagnostics(&uri, text))?;
+                    documents.insert(uri, text.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                write_message(&mut output, publish_diagnostics(&uri, ""))?;
+            }
+            "textDocument/hover" => {
+                let result = match (documents.get(&uri), params.get("position")) {
+                    (Some(text), Some(position)) => hover(text, position),
+                    _ => Json::Null,
+                };
+                write_message(&mut output, response(id.unwrap_or(Json::Null), result))?;
+            }
+            "shutdown" => write_message(&mut output, response(id.unwrap_or(Json::Null), Json::Null))?,
+            "exit" => return Ok(()),
+            method => {
+                // Notifications we do not handle are dropped; requests get
+                // a "method not found" error.
+                if let Some(id) = id {
+                    write_message(&mut output, error_response(id, -32601, &format!("unknown method {}", method)))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,15 +1,473 @@
-use untyped::parser::{Lexer, Parser};
-use std::borrow::Borrow;
-use untyped::core::eval;
+mod bench;
+mod compile;
+mod dot;
+mod gen;
+mod golden;
+mod json;
+mod lang;
+mod lsp;
+mod repl;
+mod run;
+mod serve;
 
-fn main() {
-    let tmp_str = "((λ x.(λ y. (y x))) (λ x. x)) (λ x.(λ y. (y x)))";
-    let mut lex = Lexer::new(tmp_str.chars());
-    lex.lex_input();
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::lang::Lang;
+use crate::repl::Repl;
+use crate::run::Format;
+
+#[global_allocator]
+static ALLOCATOR: bench::Counting = bench::Counting;
+
+const USAGE: &str = "\
+usage: tapl [--lang <lang>]                  start a repl
+       tapl run [--lang <lang>] [--format <text|json>] <file>
+                                             run every command in a file
+       tapl test [--lang <lang>] [--bless] <dir>
+                                             compare every *.f file under <dir>
+                                             with its .expected output
+       tapl dot [--lang <lang>] [--steps] <file>
+                                             print each command's syntax tree (or,
+                                             with --steps, its reduction sequence)
+                                             as a Graphviz DOT graph
+       tapl compile <file>                   print each arith command's stack-machine
+                                             code and the value the VM computes
+       tapl gen --lang tyarith --type <Nat|Bool> --size <n> [--seed <n>]
+                                             print a random well-typed term of size
+                                             at most <n>
+       tapl bench [--lang <arith|untyped>] [<file>]
+                                             time the evaluators on the terms in
+                                             <file>, or on generated terms
+       tapl lsp                              run a simplebool language server on stdio
+       tapl serve --stdio                    answer JSON-RPC requests on stdio
+
+languages: arith, tyarith, untyped, fulluntyped, simplebool";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+struct Args {
+    lang: Lang,
+    bless: bool,
+    steps: bool,
+    format: Format,
+    ty: Option<String>,
+    size: Option<usize>,
+    seed: Option<u64>,
+    rest: Vec<String>,
+}
+
+fn number<N: std::str::FromStr>(arg: Option<&String>) -> N {
+    match arg.map(|a| a.parse()) {
+        Some(Ok(n)) => n,
+        Some(Err(_)) => {
+            eprintln!("error: `{}` is not a number", arg.unwrap());
+            usage();
+        }
+        None => usage(),
+    }
+}
 
-    let mut parser = Parser::new(lex.result.into_iter());
-    let out = parser.parse();
-    println!("{:?}\n", eval(out.unwrap().borrow(), parser.ctx.borrow()));
+fn parse_args(args: &[String]) -> Args {
+    let mut result = Args { lang: Lang::Untyped, bless: false, steps: false, format: Format::Text, ty: None, size: None, seed: None, rest: vec![] };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--lang" => {
+                result.lang = match iter.next().map(|l| l.parse()) {
+                    Some(Ok(lang)) => lang,
+                    Some(Err(err)) => {
+                        eprintln!("error: {}", err);
+                        usage();
+                    }
+                    None => usage(),
+                }
+            }
+            "--format" => {
+                result.format = match iter.next().map(|f| f.parse()) {
+                    Some(Ok(format)) => format,
+                    Some(Err(err)) => {
+                        eprintln!("error: {}", err);
+                        usage();
+                    }
+                    None => usage(),
+                }
+            }
+            "--type" => result.ty = Some(iter.next().cloned().unwrap_or_else(|| usage())),
+            "--size" => result.size = Some(number(iter.next())),
+            "--seed" => result.seed = Some(number(iter.next())),
+            "--bless" => result.bless = true,
+            "--steps" => result.steps = true,
+            "-h" | "--help" => usage(),
+            _ if arg.starts_with("--") => {
+                eprintln!("error: unknown option `{}`", arg);
+                usage();
+            }
+            _ => result.rest.push(arg.to_owned()),
+        }
+    }
+    result
+}
+
+fn main() {
+    lang::install_panic_hook();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        Some("run") => {
+            let args = parse_args(&args[1..]);
+            if args.rest.len() != 1 {
+                usage();
+            }
+            if !run::run_file(args.lang, &args.rest[0], args.format) {
+                process::exit(1);
+            }
+        }
+        Some("test") => {
+            let args = parse_args(&args[1..]);
+            if args.rest.len() != 1 {
+                usage();
+            }
+            if !golden::test_dir(args.lang, &args.rest[0], args.bless) {
+                process::exit(1);
+            }
+        }
+        Some("dot") => {
+            let args = parse_args(&args[1..]);
+            if args.rest.len() != 1 {
+                usage();
+            }
+            let graphs = fs::read_to_string(&args.rest[0])
+                .map_err(|err| format!("cannot read {}: {}", args.rest[0], err))
+                .and_then(|source| dot::export(args.lang, &source, args.steps));
+            match graphs {
+                Ok(graphs) => print!("{}", graphs.concat()),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some("compile") => {
+            let args = parse_args(&args[1..]);
+            if args.rest.len() != 1 {
+                usage();
+            }
+            let listings = fs::read_to_string(&args.rest[0])
+                .map_err(|err| format!("cannot read {}: {}", args.rest[0], err))
+                .and_then(|source| compile::listing(Lang::Arith, &source));
+            match listings {
+                Ok(listings) => print!("{}", listings.join("\n")),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some("gen") => {
+            let args = parse_args(&args[1..]);
+            let (ty, size) = match (&args.ty, args.size) {
+                (Some(ty), Some(size)) if args.rest.is_empty() => (ty, size),
+                _ => usage(),
+            };
+            let seed = args.seed.unwrap_or_else(|| {
+                SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+            });
+            match gen::generate(args.lang, ty, size, seed) {
+                Ok(term) => println!("{}", term),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some("bench") => {
+            let args = parse_args(&args[1..]);
+            if args.rest.len() > 1 {
+                usage();
+            }
+            if !bench::run(args.lang, args.rest.first().map(|p| p.as_str())) {
+                process::exit(1);
+            }
+        }
+        Some("lsp") => {
+            let stdin = io::stdin();
+            if let Err(err) = lsp::serve(stdin.lock(), io::stdout()) {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        }
+        Some("serve") => {
+            if args[1..] != ["--stdio"] {
+                usage();
+            }
+            let stdin = io::stdin();
+            if let Err(err) = serve::serve(stdin.lock(), io::stdout()) {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        }
+        _ => {
+            let args = parse_args(&args);
+            if !args.rest.is_empty() {
+                usage();
+            }
+            if let Err(err) = Repl::new(args.lang).run() {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::bench;
+    use crate::compile;
+    use crate::dot;
+    use crate::gen;
+    use crate::golden::{check_dir, Status};
+    use crate::json::Json;
+    use crate::lsp;
+    use crate::lang::{self, Lang, LANGS};
+    use crate::repl::Repl;
+    use crate::run::{output, Format};
+    use crate::serve::Server;
+
+    #[test]
+    fn repl_test() {
+        let mut repl = Repl::new(Lang::Untyped);
+        assert_eq!(repl.command("(λ x. (x x)) (λ x. x)"), Some(vec![String::from("(λ x. x)")]));
+
+        repl.command(":lang simplebool");
+        assert_eq!(repl.lang, Lang::SimpleBool);
+        assert_eq!(repl.command("(λ x:Bool. x) true"), Some(vec![String::from("true : Bool")]));
+        assert_eq!(repl.command(":type λ x:Bool. x"), Some(vec![String::from("(Bool -> Bool)")]));
+        assert_eq!(repl.command(":step if true then false else true"), Some(vec![String::from("(if true false true)"), String::from("-> false")]));
+
+        repl.command(":lang tyarith");
+        assert_eq!(repl.command("iszero pred zero"), Some(vec![String::from("true : Bool")]));
+        assert_eq!(repl.command("succ true"), Some(vec![String::from("error: argument of succ has type Bool, expected Nat")]));
+        assert_eq!(repl.command("iszero zero; succ zero;"), Some(vec![String::from("true : Bool"), String::from("1 : Nat")]));
+        assert_eq!(repl.command(":quit"), None);
+
+        repl.command(":lang arith");
+        assert_eq!(repl.command("pred 1000; 18446744073709551615"), Some(vec![String::from("error: Number too large `18446744073709551615`, literals go up to 1000")]));
+
+        repl.command(":lang untyped");
+        assert_eq!(repl.command("x 1"), Some(vec![String::from("error: Unknown character `1`")]));
+        let steps = repl.command(":step (λ x. x x) (λ x. x x)").unwrap();
+        assert_eq!((steps.len(), steps.last().unwrap().as_str()), (lang::STEP_LIMIT + 2, "error: step limit reached"));
+
+        let path = std::env::temp_dir().join(format!("tapl-repl-test-{}.f", std::process::id()));
+        std::fs::write(&path, "λ x. x;\n:quit\n").unwrap();
+        let out = repl.command(&format!(":load {}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(out, Some(vec![format!("error: {}:2: repl commands cannot be loaded", path.display())]));
+    }
+
+    // Only the untyped languages are held to `STEP_LIMIT`.
+    #[test]
+    fn step_limit_test() {
+        let preds = "pred ".repeat(lang::STEP_LIMIT + 5);
+        let run = move || {
+            let value = |lang: Lang, input: String| lang.run(&input).unwrap().remove(0).map(|out| out.value);
+            assert_eq!(value(Lang::Arith, format!("{}0", preds)), Ok(String::from("0")));
+            assert_eq!(value(Lang::TyArith, format!("iszero {}0", preds)), Ok(String::from("true")));
+            assert_eq!(value(Lang::Untyped, String::from("(λ x. x x) (λ x. x x)")), Err(String::from("step limit reached")));
+        };
+        // The evaluators recurse once per nested `pred`.
+        std::thread::Builder::new().stack_size(1 << 26).spawn(run).unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn json_test() {
+        let (lines, ok) = output(Lang::SimpleBool, "λ x:Bool. x; true true", Format::Json);
+        assert!(!ok);
+        assert_eq!(lines[0], concat!(
+            r#"{"term":{"kind":"TmAbs","name":"x","type":{"kind":"TyBool"},"body":{"kind":"TmVar","index":0,"ctxlen":1,"name":"x"}},"#,
+            r#""type":{"kind":"TyArr","from":{"kind":"TyBool"},"to":{"kind":"TyBool"}},"#,
+            r#""value":{"kind":"TmAbs","name":"x","type":{"kind":"TyBool"},"body":{"kind":"TmVar","index":0,"ctxlen":1,"name":"x"}},"#,
+            r#""text":"(λ x: Bool. x) : (Bool -> Bool)"}"#,
+        ));
+        assert_eq!(lines[1], r#"{"error":"TyErr"}"#);
+    }
+
+    fn lsp_message(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn lsp_test() {
+        let text = r#"(λ x:Bool. x) true;\nif true then false else (λ y:Bool. y);"#;
+        let input = [
+            lsp_message(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#),
+            lsp_message(&format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"a.f","text":"{}"}}}}}}"#, text)),
+            lsp_message(r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/hover","params":{"textDocument":{"uri":"a.f"},"position":{"line":0,"character":1}}}"#),
+            lsp_message(r#"{"jsonrpc":"2.0","method":"exit"}"#),
+        ].concat();
+        let mut output = vec![];
+        lsp::serve(input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let bodies: Vec<Json> = output.split("Content-Length: ").skip(1)
+            .map(|m| Json::parse(&m[m.find("\r\n\r\n").unwrap() + 4..]).unwrap())
+            .collect();
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(bodies[1].to_string(), concat!(
+            r#"{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"a.f","diagnostics":[{"#,
+            r#""range":{"start":{"line":1,"character":0},"end":{"line":1,"character":37}},"severity":1,"source":"simplebool","#,
+            r#""message":"type error in (if true false (λ y: Bool. y))"}]}}"#,
+        ));
+        assert_eq!(bodies[2].to_string(), concat!(
+            r#"{"jsonrpc":"2.0","id":2,"result":{"contents":{"kind":"plaintext","value":"(Bool -> Bool)"},"#,
+            r#""range":{"start":{"line":0,"character":1},"end":{"line":0,"character":12}}}}"#,
+        ));
+    }
+
+    #[test]
+    fn lsp_unbalanced_test() {
+        for text in ["(true", "(λ x:Bool. x", "λ x:Bool. (x"].iter() {
+            let input = [
+                lsp_message(&format!(r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"a.f","text":"{}"}}}}}}"#, text)),
+                lsp_message(r#"{"jsonrpc":"2.0","method":"exit"}"#),
+            ].concat();
+            let mut output = vec![];
+            lsp::serve(input.as_bytes(), &mut output).unwrap();
+
+            let output = String::from_utf8(output).unwrap();
+            let body = Json::parse(&output[output.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+            let end = text.chars().count() as i64;
+            assert_eq!(body.to_string(), format!(concat!(
+                r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"a.f","diagnostics":[{{"#,
+                r#""range":{{"start":{{"line":0,"character":{0}}},"end":{{"line":0,"character":{0}}}}},"severity":1,"source":"simplebool","#,
+                r#""message":"Expected `)`"}}]}}}}"#,
+            ), end), "{}", text);
+        }
+    }
+
+    #[test]
+    fn serve_test() {
+        let mut server = Server::new();
+        let mut call = |request: &str| server.handle(request).map(|r| r.to_string());
+
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":1,"method":"bind","params":{"lang":"simplebool","context":"nb","name":"b","type":"Bool -> Bool"}}"#),
+            Some(String::from(r#"{"jsonrpc":"2.0","id":1,"result":{"name":"b"}}"#)),
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":2,"method":"typecheck","params":{"lang":"simplebool","context":"nb","source":"b true; b"}}"#),
+            Some(String::from(concat!(
+                r#"{"jsonrpc":"2.0","id":2,"result":{"types":[{"text":"Bool","ast":{"kind":"TyBool"}},"#,
+                r#"{"text":"(Bool -> Bool)","ast":{"kind":"TyArr","from":{"kind":"TyBool"},"to":{"kind":"TyBool"}}}]}}"#,
+            ))),
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":3,"method":"typecheck","params":{"lang":"simplebool","source":"b"}}"#),
+            Some(String::from(r#"{"jsonrpc":"2.0","id":3,"error":{"code":-32000,"message":"Unbound variable `b`"}}"#)),
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":4,"method":"step","params":{"lang":"arith","source":"pred (succ zero)","limit":5}}"#),
+            Some(String::from(concat!(
+                r#"{"jsonrpc":"2.0","id":4,"result":{"steps":[{"text":"pred 1","ast":{"kind":"Pred","term":{"kind":"Succ","term":{"kind":"Zero"}}}},"#,
+                r#"{"text":"0","ast":{"kind":"Zero"}}],"normal":true}}"#,
+            ))),
+        );
+        assert_eq!(call(r#"{"jsonrpc":"2.0","method":"eval","params":{"lang":"arith","source":"zero"}}"#), None);
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":5,"method":"eval","params":{"lang":"arith","context":"nb","source":"zero"}}"#),
+            Some(String::from(r#"{"jsonrpc":"2.0","id":5,"error":{"code":-32602,"message":"context `nb` is a simplebool context"}}"#)),
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":6,"method":"eval","params":{"lang":"untyped","source":"(λ x. x x) (λ x. x x)"}}"#),
+            Some(String::from(r#"{"jsonrpc":"2.0","id":6,"result":{"results":[{"error":"step limit reached"}]}}"#)),
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":7,"method":"eval","params":{"lang":"untyped","source":"\\x. x"}}"#),
+            Some(String::from(r#"{"jsonrpc":"2.0","id":7,"error":{"code":-32000,"message":"Unknown character `\\`"}}"#)),
+        );
+        assert_eq!(
+            call(r#"{"jsonrpc":"2.0","id":8,"method":"eval","params":{"lang":"fulluntyped","source":"\\x. x"}}"#),
+            Some(String::from(r#"{"jsonrpc":"2.0","id":8,"error":{"code":-32000,"message":"Unknown character `\\`"}}"#)),
+        );
+    }
+
+    #[test]
+    fn bench_test() {
+        let (rows, total) = bench::bench(Lang::Arith, "pred (succ zero); succ true; if false then zero").unwrap();
+        assert_eq!(total, 3);
+        assert_eq!((rows[0].evaluator, rows[0].terms, rows[0].steps, rows[0].peak_size), ("arith::arith::eval", 3, 1, Some(3)));
+        assert_eq!((rows[1].evaluator, rows[1].terms, rows[1].steps, rows[1].peak_size), ("arith::arith::big_eval", 3, 1, None));
+        assert_eq!((rows[2].evaluator, rows[2].terms, rows[2].steps), ("arith::arith::eval_iterative", 3, 1));
+        assert!(rows[0].allocs > 0);
+
+        let (rows, total) = bench::bench(Lang::Untyped, &bench::untyped_corpus(5)).unwrap();
+        assert_eq!((total, rows.len(), rows[0].terms), (5, 1, 5));
+
+        assert!(bench::bench(Lang::SimpleBool, "true").is_err());
+    }
+
+    #[test]
+    fn compile_test() {
+        assert_eq!(compile::listing(Lang::Arith, "pred 2; if false then 0"), Ok(vec![
+            String::from("0000  push_zero\n0001  succ\n0002  succ\n0003  pred\n; pred 2 => 1\n"),
+            String::from(concat!(
+                "0000  push_false\n0001  jump_if_false 0004\n0002  push_zero\n0003  jump 0005\n0004  no_else\n",
+                "; if false then 0 => error at 0004: the condition is false and there is no else branch\n",
+            )),
+        ]));
+        assert!(compile::listing(Lang::Arith, "succ (").is_err());
+        assert!(compile::listing(Lang::TyArith, "0").is_err());
+    }
+
+    #[test]
+    fn gen_test() {
+        let term = gen::generate(Lang::TyArith, "Nat", 20, 7).unwrap();
+        assert_eq!(gen::generate(Lang::TyArith, "Nat", 20, 7), Ok(term.clone()));
+        let mut repl = Repl::new(Lang::TyArith);
+        assert!(repl.command(&term).unwrap()[0].ends_with(" : Nat"), "{}", term);
+        assert!(gen::generate(Lang::TyArith, "Bool -> Bool", 20, 7).is_err());
+        assert!(gen::generate(Lang::TyArith, "Bool", 0, 7).is_err());
+        assert!(gen::generate(Lang::Arith, "Nat", 20, 7).is_err());
+    }
+
+    #[test]
+    fn dot_test() {
+        assert_eq!(dot::export(Lang::Arith, "succ zero", false), Ok(vec![String::from(concat!(
+            "digraph term {\n",
+            "  node [shape=box, fontname=\"monospace\"];\n",
+            "  n0 [label=\"succ\"];\n",
+            "  n1 [label=\"0\"];\n",
+            "  n0 -> n1;\n",
+            "}\n",
+        ))]));
+
+        let graphs = dot::export(Lang::Untyped, "λ x. λ y. x", false).unwrap();
+        assert!(graphs[0].contains("n2 [label=\"x #1\"];"));
+
+        let graphs = dot::export(Lang::SimpleBool, "(λ x:Bool. x) (if true then false else true)", true).unwrap();
+        assert!(graphs[0].contains("s0n0 [label=\"app\"];"));
+        assert!(graphs[0].contains("s0n3 [label=\"if\", style=filled, fillcolor=\"#ffd27f\"];"));
+        assert!(graphs[0].contains("s1n0 [label=\"app\", style=filled, fillcolor=\"#ffd27f\"];"));
+        assert!(graphs[0].contains("s2n0 [label=\"false\"];"));
+        assert!(graphs[0].contains("{ rank=same; s0n0; s1n0; s2n0; }"));
+
+        assert!(dot::export(Lang::TyArith, "zero", false).is_err());
+    }
+
+    #[test]
+    fn golden_test() {
+        for lang in LANGS.iter() {
+            let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join(lang.to_string());
+            for report in check_dir(*lang, &dir, false).unwrap() {
+                assert_eq!(report.status, Status::Passed, "{}", report.path.display());
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use crate::lang::{Lang, LANGS};
+
+const HELP: &str = "\
+commands:
+  <term>; ...     evaluate terms (and show their types, if the language has one)
+  :type <term>    show the type of a term
+  :step <term>    show the small-step reductions of a term, up to 1000 of them
+  :lang <name>    switch language
+  :load <file>    run every command in a file
+  :help           show this message
+  :quit           leave the repl";
+
+pub struct Repl {
+    pub lang: Lang,
+}
+
+impl Repl {
+    pub fn new(lang: Lang) -> Self {
+        Repl {
+            lang
+        }
+    }
+
+    /// Handles one line of input, returning the lines to print, or `None`
+    /// when the user asks to quit.
+    pub fn command(&mut self, line: &str) -> Option<Vec<String>> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Some(vec![]);
+        }
+        if !line.starts_with(':') {
+            return Some(self.eval(line));
+        }
+
+        let (cmd, arg) = match line.find(' ') {
+            Some(idx) => (&line[..idx], line[idx..].trim()),
+            None => (line, ""),
+        };
+        let out = match cmd {
+            ":quit" | ":q" => return None,
+            ":help" | ":h" => vec![HELP.to_string()],
+            ":lang" => self.switch_lang(arg),
+            ":type" | ":t" => vec![show(self.lang.type_of(arg))],
+            ":step" | ":s" => match self.lang.steps(arg) {
+                Ok((steps, normal)) => {
+                    let mut out: Vec<String> = steps.iter().enumerate()
+                        .map(|(i, t)| if i == 0 { t.to_owned() } else { format!("-> {}", t) })
+                        .collect();
+                    if !normal {
+                        out.push(String::from("error: step limit reached"));
+                    }
+                    out
+                }
+                Err(err) => vec![format!("error: {}", err)],
+            },
+            ":load" | ":l" => self.load(arg),
+            _ => vec![format!("error: unknown command `{}`, try :help", cmd)],
+        };
+        Some(out)
+    }
+
+    fn switch_lang(&mut self, arg: &str) -> Vec<String> {
+        if arg.is_empty() {
+            let names = LANGS.iter().map(|l| l.to_string()).collect::<Vec<String>>().join(", ");
+            return vec![format!("current language: {} (available: {})", self.lang, names)];
+        }
+        match arg.parse::<Lang>() {
+            Ok(lang) => {
+                self.lang = lang;
+                vec![format!("switched to {}", lang)]
+            }
+            Err(err) => vec![format!("error: {}", err)],
+        }
+    }
+
+    fn eval(&self, input: &str) -> Vec<String> {
+        match self.lang.run(input) {
+            Ok(results) => results.into_iter().map(show).collect(),
+            Err(err) => vec![format!("error: {}", err)],
+        }
+    }
+
+    // A loaded file is a program, not a repl session: a line such as
+    // `:quit` is reported instead of run.
+    fn load(&self, path: &str) -> Vec<String> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => return vec![format!("error: cannot read {}: {}", path, err)],
+        };
+        match source.lines().position(|l| l.trim_start().starts_with(':')) {
+            Some(n) => vec![format!("error: {}:{}: repl commands cannot be loaded", path, n + 1)],
+            None => self.eval(&source),
+        }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let mut stdout = io::stdout();
+        let mut line = String::new();
+
+        loop {
+            write!(stdout, "{}> ", self.lang)?;
+            stdout.flush()?;
+
+            line.clear();
+            if stdin.lock().read_line(&mut line)? == 0 {
+                writeln!(stdout)?;
+                return Ok(());
+            }
+            match self.command(&line) {
+                Some(out) => {
+                    for l in out {
+                        writeln!(stdout, "{}", l)?;
+                    }
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+fn show<T: ToString>(result: Result<T, String>) -> String {
+    match result {
+        Ok(out) => out.to_string(),
+        Err(err) => format!("error: {}", err),
+    }
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::str::FromStr;
+use crate::json::Json;
+use crate::lang::Lang;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format `{}`", s)),
+        }
+    }
+}
+
+fn error(format: Format, err: String) -> String {
+    match format {
+        Format::Text => format!("error: {}", err),
+        Format::Json => Json::object(vec![("error", Json::Str(err))]).to_string(),
+    }
+}
+
+/// Runs every command of `source`, returning the lines `tapl run` prints
+/// for it and whether all commands succeeded. In JSON format every line
+/// is one object.
+pub fn output(lang: Lang, source: &str, format: Format) -> (Vec<String>, bool) {
+    match lang.run(source) {
+        Ok(results) => {
+            let mut ok = true;
+            let lines = results.into_iter().map(|result| match result {
+                Ok(out) => match format {
+                    Format::Text => out.to_string(),
+                    Format::Json => out.to_json().to_string(),
+                },
+                Err(err) => {
+                    ok = false;
+                    error(format, err)
+                }
+            }).collect();
+            (lines, ok)
+        }
+        Err(err) => (vec![error(format, err)], false),
+    }
+}
+
+/// Runs every command of the program in `path`, printing one result per
+/// command. Returns whether all of them succeeded.
+pub fn run_file(lang: Lang, path: &str, format: Format) -> bool {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", path, err);
+            return false;
+        }
+    };
+
+    let (lines, ok) = output(lang, &source, format);
+    for line in lines {
+        println!("{}", line);
+    }
+    ok
+}
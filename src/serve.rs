@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use crate::json::Json;
+use crate::lang::{Lang, Session, Shown};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Errors reported by the language itself, e.g. a term that does not parse.
+const LANGUAGE_ERROR: i64 = -32000;
+
+const DEFAULT_STEP_LIMIT: usize = 1;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: String) -> Self {
+        RpcError { code, message }
+    }
+}
+
+fn shown(shown: Shown) -> Json {
+    Json::object(vec![("text", Json::Str(shown.text)), ("ast", shown.ast)])
+}
+
+fn error(message: String) -> Json {
+    Json::object(vec![("error", Json::Str(message))])
+}
+
+/// Answers JSON-RPC requests, keeping every named session, and the free
+/// variables bound in it, alive for the lifetime of the server.
+pub struct Server {
+    sessions: HashMap<String, Session>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server {
+            sessions: HashMap::new()
+        }
+    }
+
+    fn param<'a>(params: &'a Json, name: &str) -> Result<&'a str, RpcError> {
+        params.get(name).and_then(|p| p.as_str())
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("missing string parameter `{}`", name)))
+    }
+
+    // Requests naming a `context` share its session; requests without one
+    // get a fresh session.
+    fn session(&mut self, params: &Json) -> Result<&mut Session, RpcError> {
+        let lang = Server::param(params, "lang")?.parse::<Lang>()
+            .map_err(|err| RpcError::new(INVALID_PARAMS, err))?;
+        let name = match params.get("context") {
+            Some(Json::Str(name)) => name.clone(),
+            None | Some(Json::Null) => String::new(),
+            _ => return Err(RpcError::new(INVALID_PARAMS, String::from("`context` must be a string"))),
+        };
+
+        if name.is_empty() {
+            self.sessions.insert(String::new(), Session::new(lang));
+        }
+        let session = self.sessions.entry(name.clone()).or_insert_with(|| Session::new(lang));
+        if session.lang != lang {
+            return Err(RpcError::new(INVALID_PARAMS, format!("context `{}` is a {} context", name, session.lang)));
+        }
+        Ok(session)
+    }
+
+    fn call(&mut self, method: &str, params: &Json) -> Result<Json, RpcError> {
+        let language_error = |err| RpcError::new(LANGUAGE_ERROR, err);
+        match method {
+            "parse" => {
+                let source = Server::param(params, "source")?;
+                let terms = self.session(params)?.parse(source).map_err(language_error)?;
+                Ok(Json::object(vec![("terms", Json::Array(terms.into_iter().map(shown).collect()))]))
+            }
+            "typecheck" => {
+                let source = Server::param(params, "source")?;
+                let types = self.session(params)?.type_of(source).map_err(language_error)?;
+                let types = types.into_iter().map(|ty| ty.map_or_else(error, shown)).collect();
+                Ok(Json::object(vec![("types", Json::Array(types))]))
+            }
+            "eval" => {
+                let source = Server::param(params, "source")?;
+                let results = self.session(params)?.run(source).map_err(language_error)?;
+                let results = results.into_iter().map(|r| r.map_or_else(error, |out| out.to_json())).collect();
+                Ok(Json::object(vec![("results", Json::Array(results))]))
+            }
+            "step" => {
+                let source = Server::param(params, "source")?;
+                let limit = match params.get("limit") {
+                    Some(limit) => match limit.as_i64() {
+                        Some(n) if n >= 0 => n as usize,
+                        _ => return Err(RpcError::new(INVALID_PARAMS, String::from("`limit` must be a non-negative number"))),
+                    },
+                    None => DEFAULT_STEP_LIMIT,
+                };
+                let (steps, normal) = self.session(params)?.steps(source, limit).map_err(language_error)?;
+                Ok(Json::object(vec![
+                    ("steps", Json::Array(steps.into_iter().map(shown).collect())),
+                    ("normal", Json::Bool(normal)),
+                ]))
+            }
+            "bind" => {
+                let name = Server::param(params, "name")?;
+                let ty = params.get("type").and_then(|t| t.as_str());
+                let bound = self.session(params)?.bind(name, ty).map_err(language_error)?;
+                Ok(Json::object(vec![("name", Json::Str(bound))]))
+            }
+            "reset" => {
+                let name = Server::param(params, "context")?;
+                Ok(Json::Bool(self.sessions.remove(name).is_some()))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        }
+    }
+
+    /// Handles one request and returns its response, or `None` for a
+    /// notification (a request without an `id`).
+    pub fn handle(&mut self, request: &str) -> Option<Json> {
+        let request = match Json::parse(request) {
+            Ok(request) => request,
+            Err(err) => return Some(response(Json::Null, Err(RpcError::new(PARSE_ERROR, err)))),
+        };
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(|m| m.as_str()) {
+            Some(method) => {
+                let params = request.get("params").cloned().unwrap_or(Json::Null);
+                self.call(method, &params)
+            }
+            None => Err(RpcError::new(INVALID_REQUEST, String::from("missing `method`"))),
+        };
+        id.map(|id| response(id, result))
+    }
+}
+
+fn response(id: Json, result: Result<Json, RpcError>) -> Json {
+    let (key, value) = match result {
+        Ok(result) => ("result", result),
+        Err(RpcError { code, message }) => {
+            ("error", Json::object(vec![("code", Json::Number(code)), ("message", Json::Str(message))]))
+        }
+    };
+    Json::object(vec![("jsonrpc", Json::str("2.0")), ("id", id), (key, value)])
+}
+
+/// Reads one JSON-RPC request per line from `input` and writes one
+/// response per line to `output`, until `input` is closed.
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut server = Server::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle(&line) {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
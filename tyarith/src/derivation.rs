@@ -0,0 +1,220 @@
+use std::fmt;
+use crate::tyarith::{Prim, Term, Type};
+
+/// The typing rules of TAPL figures 8-1 and 8-2, plus T-Num for literals
+/// and one rule per primitive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    True,
+    False,
+    If,
+    Zero,
+    Succ,
+    Pred,
+    IsZero,
+    Num,
+    Prim(Prim),
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Rule::True => "T-True",
+            Rule::False => "T-False",
+            Rule::If => "T-If",
+            Rule::Zero => "T-Zero",
+            Rule::Succ => "T-Succ",
+            Rule::Pred => "T-Pred",
+            Rule::IsZero => "T-IsZero",
+            Rule::Num => "T-Num",
+            Rule::Prim(Prim::Plus) => "T-Plus",
+            Rule::Prim(Prim::Times) => "T-Times",
+            Rule::Prim(Prim::Minus) => "T-Minus",
+            Rule::Prim(Prim::Eq) => "T-Eq",
+            Rule::Prim(Prim::Lt) => "T-Lt",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A derivation of `term : ty`, with one premise per subterm.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Derivation {
+    pub rule: Rule,
+    pub term: Term,
+    pub ty: Type,
+    pub premises: Vec<Derivation>,
+}
+
+impl Derivation {
+    /// The derivation as indented text, the conclusion first and its
+    /// premises one level further in.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        self.write_text(&mut out, 0);
+        out
+    }
+
+    fn write_text(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!("{}{} : {}  [{}]\n", "  ".repeat(depth), self.term, self.ty, self.rule));
+        for premise in &self.premises {
+            premise.write_text(out, depth + 1);
+        }
+    }
+
+    /// The derivation as a `prooftree` for the LaTeX bussproofs package.
+    pub fn to_latex(&self) -> String {
+        let mut out = String::from("\\begin{prooftree}\n");
+        self.write_latex(&mut out);
+        out.push_str("\\end{prooftree}\n");
+        out
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        if self.premises.is_empty() {
+            out.push_str("\\AxiomC{}\n");
+        }
+        for premise in &self.premises {
+            premise.write_latex(out);
+        }
+        let inference = match self.premises.len() {
+            0 | 1 => "UnaryInfC",
+            2 => "BinaryInfC",
+            _ => "TrinaryInfC",
+        };
+        out.push_str(&format!("\\RightLabel{{\\textsc{{{}}}}}\n", self.rule));
+        out.push_str(&format!("\\{}{{$\\vdash {} : {}$}}\n", inference, latex(&self.term), latex_type(self.ty)));
+    }
+}
+
+/// Where in its parent the ill-typed subterm of a `TypeError` sits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Position {
+    SuccArgument,
+    PredArgument,
+    IsZeroArgument,
+    FirstArgument(Prim),
+    SecondArgument(Prim),
+    Condition,
+    // The else branch is expected to have the type of the then branch.
+    ElseBranch,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Position::SuccArgument => write!(f, "argument of succ"),
+            Position::PredArgument => write!(f, "argument of pred"),
+            Position::IsZeroArgument => write!(f, "argument of iszero"),
+            Position::FirstArgument(prim) => write!(f, "first argument of {}", prim),
+            Position::SecondArgument(prim) => write!(f, "second argument of {}", prim),
+            Position::Condition => write!(f, "condition of if"),
+            Position::ElseBranch => write!(f, "else branch of if"),
+        }
+    }
+}
+
+/// The first subterm, in evaluation order, whose type does not fit where
+/// it appears.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeError {
+    pub position: Position,
+    pub subterm: Term,
+    pub expected: Type,
+    pub found: Type,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} has type {}, expected {}", self.position, self.found, self.expected)
+    }
+}
+
+impl TypeError {
+    pub fn to_text(&self) -> String {
+        format!("{}\n  {} : {}\n", self, self.subterm, self.found)
+    }
+
+    pub fn to_latex(&self) -> String {
+        format!(
+            "{} has type ${}$, expected ${}$: $\\vdash {} : {}$\n",
+            self.position,
+            latex_type(self.found),
+            latex_type(self.expected),
+            latex(&self.subterm),
+            latex_type(self.found),
+        )
+    }
+}
+
+// Prints a term as LaTeX math in TAPL's notation.
+fn latex(t: &Term) -> String {
+    let keyword = |k: &str| format!("\\mathsf{{{}}}", k);
+    let atom = |t: &Term| match t {
+        Term::True | Term::False | Term::Zero | Term::Num(_) => latex(t),
+        _ => format!("({})", latex(t)),
+    };
+    let parts = match t {
+        Term::True => vec![keyword("true")],
+        Term::False => vec![keyword("false")],
+        Term::Zero => vec![String::from("0")],
+        Term::Num(n) => vec![n.to_string()],
+        Term::Prim(prim, t1, t2) => vec![keyword(&prim.to_string()), atom(t1), atom(t2)],
+        Term::Succ(t1) => vec![keyword("succ"), atom(t1)],
+        Term::Pred(t1) => vec![keyword("pred"), atom(t1)],
+        Term::IsZero(t1) => vec![keyword("iszero"), atom(t1)],
+        Term::If(t1, t2, t3) => {
+            let mut parts = vec![keyword("if"), atom(t1), keyword("then"), atom(t2)];
+            if let Some(t3) = t3 {
+                parts.push(keyword("else"));
+                parts.push(atom(t3));
+            }
+            parts
+        }
+    };
+    parts.join("\\ ")
+}
+
+fn latex_type(ty: Type) -> String {
+    format!("\\mathsf{{{}}}", ty)
+}
+
+// Derives `t1` and checks it has type `expected` in `position`.
+fn expect(t1: &Term, position: Position, expected: Type) -> Result<Derivation, TypeError> {
+    let d = derive(t1)?;
+    if d.ty == expected {
+        Ok(d)
+    } else {
+        Err(TypeError { position, subterm: t1.clone(), expected, found: d.ty })
+    }
+}
+
+/// Like `type_check`, but returns the typing derivation, or the subterm
+/// that makes `term` ill-typed.
+pub fn derive(term: &Term) -> Result<Derivation, TypeError> {
+    let derivation = |rule, ty, premises| Ok(Derivation { rule, term: term.clone(), ty, premises });
+    match term {
+        Term::True => derivation(Rule::True, Type::Bool, vec![]),
+        Term::False => derivation(Rule::False, Type::Bool, vec![]),
+        Term::Zero => derivation(Rule::Zero, Type::Nat, vec![]),
+        Term::Num(_) => derivation(Rule::Num, Type::Nat, vec![]),
+        Term::Prim(prim, t1, t2) => {
+            let d1 = expect(t1, Position::FirstArgument(*prim), Type::Nat)?;
+            let d2 = expect(t2, Position::SecondArgument(*prim), Type::Nat)?;
+            derivation(Rule::Prim(*prim), prim.result_type(), vec![d1, d2])
+        }
+        Term::Succ(t1) => derivation(Rule::Succ, Type::Nat, vec![expect(t1, Position::SuccArgument, Type::Nat)?]),
+        Term::Pred(t1) => derivation(Rule::Pred, Type::Nat, vec![expect(t1, Position::PredArgument, Type::Nat)?]),
+        Term::IsZero(t1) => derivation(Rule::IsZero, Type::Bool, vec![expect(t1, Position::IsZeroArgument, Type::Nat)?]),
+        Term::If(t1, t2, t3) => {
+            let d1 = expect(t1, Position::Condition, Type::Bool)?;
+            let d2 = derive(t2)?;
+            let ty = d2.ty;
+            let mut premises = vec![d1, d2];
+            if let Some(t3) = t3 {
+                premises.push(expect(t3, Position::ElseBranch, ty)?);
+            }
+            derivation(Rule::If, ty, premises)
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::rng::Rng;
+use crate::tyarith::{Prim, Term, Type};
+
+/// A seeded source of well-typed terms, drawn uniformly from all terms of
+/// a type up to a size, counted as in `metrics::size`. Every `if` has an
+/// else branch of the same type as its then branch. Numbers are built
+/// from `0` and `succ`, never as literals, and the primitives are only
+/// used by a generator made `with_primitives`.
+pub struct Generator {
+    rng: Rng,
+    primitives: bool,
+    // `nat[n]` and `bool[n]` count the terms of exactly size `n`.
+    nat: Vec<u128>,
+    bool: Vec<u128>,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator {
+            rng: Rng::new(seed),
+            primitives: false,
+            nat: vec![0],
+            bool: vec![0],
+        }
+    }
+
+    /// A generator whose terms also use `plus`, `times`, `minus`, `eq` and
+    /// `lt`. There are far more of them: sizes above 50 cannot be counted.
+    pub fn with_primitives(seed: u64) -> Self {
+        Generator { primitives: true, ..Generator::new(seed) }
+    }
+
+    // Uniform in `0..n`, rejecting the draws that would bias the modulo.
+    fn below(&mut self, n: u128) -> u128 {
+        let zone = u128::MAX - u128::MAX % n;
+        loop {
+            let r = (u128::from(self.rng.next_u64()) << 64) | u128::from(self.rng.next_u64());
+            if r < zone {
+                return r % n;
+            }
+        }
+    }
+
+    fn counts(&self, ty: Type) -> &[u128] {
+        match ty {
+            Type::Nat => &self.nat,
+            Type::Bool => &self.bool,
+        }
+    }
+
+    // The ways to split `size` between the three parts of an `if`.
+    fn if_splits(size: usize) -> impl Iterator<Item = (usize, usize, usize)> {
+        (1..size).flat_map(move |a| (1..size - a).map(move |b| (a, b, size - a - b)))
+    }
+
+    // The ways to split `size` between the two arguments of a primitive.
+    fn prim_splits(size: usize) -> impl Iterator<Item = (usize, usize)> {
+        (1..size).map(move |a| (a, size - a))
+    }
+
+    fn prim_count(&self, size: usize) -> Option<u128> {
+        if !self.primitives {
+            return Some(0);
+        }
+        Generator::prim_splits(size).try_fold(0u128, |sum, (a, b)| sum.checked_add(self.nat[a].checked_mul(self.nat[b])?))
+    }
+
+    fn if_count(&self, size: usize, cond: Type, ty: Type) -> Option<u128> {
+        Generator::if_splits(size).try_fold(0u128, |sum, (a, b, c)| {
+            let ways = self.counts(cond)[a].checked_mul(self.counts(ty)[b])?.checked_mul(self.counts(ty)[c])?;
+            sum.checked_add(ways)
+        })
+    }
+
+    // Extends the counts to `size`, panicking if they no longer fit.
+    fn fill(&mut self, size: usize) {
+        while self.nat.len() <= size {
+            let n = self.nat.len();
+            let (nat, bool) = if n == 1 {
+                (Some(1), Some(2))
+            } else {
+                let prims = self.prim_count(n - 1);
+                let nat = self.if_count(n - 1, Type::Bool, Type::Nat)
+                    .and_then(|c| c.checked_add(self.nat[n - 1].checked_mul(2)?)?.checked_add(prims?.checked_mul(3)?));
+                let bool = self.if_count(n - 1, Type::Bool, Type::Bool)
+                    .and_then(|c| c.checked_add(self.nat[n - 1])?.checked_add(prims?.checked_mul(2)?));
+                (nat, bool)
+            };
+            match (nat, bool) {
+                (Some(nat), Some(bool)) => {
+                    self.nat.push(nat);
+                    self.bool.push(bool);
+                }
+                _ => panic!("there are too many terms of size {} to count", n),
+            }
+        }
+    }
+
+    /// The number of well-typed terms of type `ty` and exactly `size`.
+    pub fn count(&mut self, ty: Type, size: usize) -> u128 {
+        self.fill(size);
+        self.counts(ty)[size]
+    }
+
+    /// A term of type `ty` and size at most `budget`, every such term
+    /// being equally likely.
+    pub fn term(&mut self, ty: Type, budget: usize) -> Term {
+        assert!(budget > 0, "there are no terms of size 0");
+        self.fill(budget);
+        let total = self.counts(ty)[1..=budget].iter().try_fold(0u128, |sum, &c| sum.checked_add(c))
+            .unwrap_or_else(|| panic!("there are too many terms of size at most {} to count", budget));
+        let mut r = self.below(total);
+        for size in 1..=budget {
+            let count = self.counts(ty)[size];
+            if r < count {
+                return self.sized(ty, size);
+            }
+            r -= count;
+        }
+        unreachable!()
+    }
+
+    // A uniformly chosen term of type `ty` and exactly `size`.
+    fn sized(&mut self, ty: Type, size: usize) -> Term {
+        let mut r = self.below(self.counts(ty)[size]);
+        if size == 1 {
+            return match (ty, r) {
+                (Type::Nat, _) => Term::Zero,
+                (Type::Bool, 0) => Term::True,
+                (Type::Bool, _) => Term::False,
+            };
+        }
+
+        let arg = self.nat[size - 1];
+        let unary = match ty {
+            Type::Nat => vec![Term::Succ as fn(Box<Term>) -> Term, Term::Pred],
+            Type::Bool => vec![Term::IsZero as fn(Box<Term>) -> Term],
+        };
+        for wrap in unary {
+            if r < arg {
+                return wrap(Box::new(self.sized(Type::Nat, size - 1)));
+            }
+            r -= arg;
+        }
+        let prims = match ty {
+            _ if !self.primitives => vec![],
+            Type::Nat => vec![Prim::Plus, Prim::Times, Prim::Minus],
+            Type::Bool => vec![Prim::Eq, Prim::Lt],
+        };
+        for prim in prims {
+            for (a, b) in Generator::prim_splits(size - 1) {
+                let ways = self.nat[a] * self.nat[b];
+                if r < ways {
+                    let t1 = self.sized(Type::Nat, a);
+                    let t2 = self.sized(Type::Nat, b);
+                    return Term::Prim(prim, Box::new(t1), Box::new(t2));
+                }
+                r -= ways;
+            }
+        }
+        for (a, b, c) in Generator::if_splits(size - 1) {
+            let ways = self.bool[a] * self.counts(ty)[b] * self.counts(ty)[c];
+            if r < ways {
+                let t1 = self.sized(Type::Bool, a);
+                let t2 = self.sized(ty, b);
+                let t3 = self.sized(ty, c);
+                return Term::If(Box::new(t1), Box::new(t2), Some(Box::new(t3)));
+            }
+            r -= ways;
+        }
+        unreachable!()
+    }
+}
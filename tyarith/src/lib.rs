@@ -1,7 +1,14 @@
 #[cfg(test)]
 mod tests {
-    use crate::tyarith::{typing, Type};
+    use crate::tyarith::{big_eval, eval, type_check, typing, Prim, Type};
+    use crate::derivation::{derive, Position, Rule, TypeError};
+    use crate::soundness::{check, Counterexample, Violation};
+    use crate::generator::Generator;
+    use crate::rng::Rng;
+    use crate::tyarith::Term;
     use crate::tyarith::Term::*;
+    use crate::metrics::{consts, consts_lemma_holds, depth, size};
+    use crate::parser::{Lexer, Parser};
 
     #[test]
     fn eval_test() {
@@ -18,7 +25,236 @@ mod tests {
     }
 
 
+    // Every term of depth at most `depth`, built as in TAPL definition 3.2.3.
+    fn terms(depth: usize) -> Vec<Term> {
+        if depth == 0 {
+            return vec![];
+        }
+        let smaller = terms(depth - 1);
+        let mut result = vec![True, False, Zero];
+        for t in smaller.iter() {
+            result.push(Succ(Box::new(t.clone())));
+            result.push(Pred(Box::new(t.clone())));
+            result.push(IsZero(Box::new(t.clone())));
+            for t2 in smaller.iter() {
+                result.push(If(Box::new(t.clone()), Box::new(t2.clone()), None));
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn display_test() {
+        let t = If(Box::new(IsZero(Box::new(Zero))), Box::new(If(Box::new(True), Box::new(Zero), None)), Some(Box::new(Zero)));
+        assert_eq!(t.to_string(), "if iszero 0 then (if true then 0) else 0");
+
+        // `terms` builds every `if` without an else branch; nesting them
+        // covers the dangling else.
+        for t in terms(3) {
+            let t = If(Box::new(True), Box::new(t), Some(Box::new(Zero)));
+            let printed = t.to_string();
+            let mut lex = Lexer::new(printed.chars());
+            lex.lex_input();
+            assert_eq!(Parser::new(lex.result.into_iter()).parse_commands(), vec![t]);
+        }
+        let t = parse("lt (plus 1 (succ 2)) (if eq 0 zero then 7 else minus 2 1)");
+        assert_eq!(t.to_string(), "lt (plus 1 3) (if eq 0 0 then 7 else minus 2 1)");
+        assert_eq!(parse(&t.to_string()), t);
+
+        // Literals are `succ` chains, held as `Num` past `MAX_CHAIN_LITERAL`;
+        // either way they equal the chain.
+        assert!(matches!(parse("2"), Succ(ref t) if matches!(**t, Succ(ref t) if matches!(**t, Zero))));
+        assert!(matches!(parse("1001"), Num(1001)));
+        assert_eq!(Term::nat(1001), Succ(Box::new(Term::nat(1000))));
+        let t = Succ(Box::new(Num(18446744073709551615)));
+        assert_eq!(t.to_string(), "succ 18446744073709551615");
+        assert_eq!(parse(&t.to_string()), t);
+    }
+
+    fn parse(input: &str) -> Term {
+        let mut lex = Lexer::new(input.chars());
+        lex.lex_input();
+        Parser::new(lex.result.into_iter()).parse_commands().remove(0)
+    }
+
+    #[test]
+    fn derive_test() {
+        let d = derive(&parse("if iszero 0 then 1 else 0")).unwrap();
+        assert_eq!((d.rule, d.ty), (Rule::If, Type::Nat));
+        assert_eq!(d.to_text(), concat!(
+            "if iszero 0 then 1 else 0 : Nat  [T-If]\n",
+            "  iszero 0 : Bool  [T-IsZero]\n",
+            "    0 : Nat  [T-Zero]\n",
+            "  1 : Nat  [T-Succ]\n",
+            "    0 : Nat  [T-Zero]\n",
+            "  0 : Nat  [T-Zero]\n",
+        ));
+        assert_eq!(derive(&parse("iszero 0")).unwrap().to_latex(), concat!(
+            "\\begin{prooftree}\n",
+            "\\AxiomC{}\n",
+            "\\RightLabel{\\textsc{T-Zero}}\n",
+            "\\UnaryInfC{$\\vdash 0 : \\mathsf{Nat}$}\n",
+            "\\RightLabel{\\textsc{T-IsZero}}\n",
+            "\\UnaryInfC{$\\vdash \\mathsf{iszero}\\ 0 : \\mathsf{Bool}$}\n",
+            "\\end{prooftree}\n",
+        ));
+
+        let err = derive(&parse("succ (if 0 then 1 else 2)")).unwrap_err();
+        assert_eq!(err, TypeError { position: Position::Condition, subterm: Zero, expected: Type::Bool, found: Type::Nat });
+        assert_eq!(err.to_string(), "condition of if has type Nat, expected Bool");
+        assert_eq!(err.to_text(), "condition of if has type Nat, expected Bool\n  0 : Nat\n");
+        assert_eq!(err.to_latex(), "condition of if has type $\\mathsf{Nat}$, expected $\\mathsf{Bool}$: $\\vdash 0 : \\mathsf{Nat}$\n");
+        assert_eq!(derive(&parse("if true then 0 else false")).unwrap_err().to_string(), "else branch of if has type Bool, expected Nat");
+        assert_eq!(derive(&parse("pred (iszero 0)")).unwrap_err().subterm, parse("iszero 0"));
+
+        for t in terms(3) {
+            assert_eq!(derive(&t).map(|d| d.ty).ok(), crate::tyarith::type_check(t.clone()).ok(), "{}", t);
+        }
+    }
+
+    #[test]
+    fn soundness_test() {
+        assert_eq!(check(&parse("succ (if true then pred 2 else 0)")), Ok(2));
+        assert_eq!(check(&parse("iszero 5")), Ok(1));
+
+        // An `if` without else is typed like one with an else branch of the
+        // same type, but gets stuck when the condition is false.
+        let err = check(&parse("if iszero 0 then (if false then 0) else 1")).unwrap_err();
+        assert_eq!(err, Counterexample {
+            term: parse("if iszero 0 then (if false then 0) else 1"),
+            step: 2,
+            violation: Violation::Progress { term: parse("if false then 0") },
+        });
+        assert_eq!(err.to_string(), "`if iszero 0 then (if false then 0) else 1` at step 2: progress fails, `if false then 0` is stuck");
+
+        for t in terms(3).into_iter().filter(|t| derive(t).is_ok()) {
+            match check(&t) {
+                Ok(_) => {}
+                Err(Counterexample { violation: Violation::Progress { term }, .. }) => {
+                    assert!(term.to_string().contains("if false then"), "{}", t);
+                }
+                Err(err) => panic!("{}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn generator_test() {
+        let mut gen = Generator::new(23);
+        let counts: Vec<(u128, u128)> = (1..=4).map(|n| (gen.count(Type::Nat, n), gen.count(Type::Bool, n))).collect();
+        assert_eq!(counts, vec![(1, 2), (2, 1), (4, 2), (10, 12)]);
+        assert!(gen.count(Type::Nat, 40) > 0);
+        assert!(gen.count(Type::Bool, 60) > 0);
+
+        // The five boolean terms of size at most 3 come up equally often.
+        let mut seen: Vec<(Term, usize)> = vec![];
+        for _ in 0..5000 {
+            let t = gen.term(Type::Bool, 3);
+            match seen.iter_mut().find(|(u, _)| *u == t) {
+                Some((_, n)) => *n += 1,
+                None => seen.push((t, 1)),
+            }
+        }
+        assert_eq!(seen.len(), 5);
+        assert!(seen.iter().all(|&(_, n)| (800..1200).contains(&n)), "{:?}", seen);
+
+        for i in 0..1000 {
+            let ty = if i % 2 == 0 { Type::Nat } else { Type::Bool };
+            let t = gen.term(ty, 20);
+            assert!(size(&t) <= 20);
+            assert_eq!(derive(&t).map(|d| d.ty), Ok(ty), "{}", t);
+            assert!(check(&t).is_ok(), "{}", t);
+        }
+
+        let mut gen = Generator::with_primitives(23);
+        let counts: Vec<(u128, u128)> = (1..=4).map(|n| (gen.count(Type::Nat, n), gen.count(Type::Bool, n))).collect();
+        assert_eq!(counts, vec![(1, 2), (2, 1), (7, 4), (28, 23)]);
+        assert!(gen.count(Type::Bool, 50) > 0);
+        for i in 0..1000 {
+            let ty = if i % 2 == 0 { Type::Nat } else { Type::Bool };
+            let t = gen.term(ty, 20);
+            assert_eq!(derive(&t).map(|d| d.ty), Ok(ty), "{}", t);
+            assert!(check(&t).is_ok(), "{}", t);
+        }
+    }
+
+    #[test]
+    fn prim_test() {
+        let cases = vec![
+            ("plus 2 (times 3 4)", Num(14)),
+            ("minus 2 5", Num(0)),
+            ("minus (succ 9) (pred 3)", Num(8)),
+            ("eq (succ 2) 3", True),
+            ("lt 1 zero", False),
+            ("if lt 1 2 then times 1000000000 1000000000 else 0", Num(1_000_000_000_000_000_000)),
+            ("pred (plus 1 1)", Num(1)),
+            ("iszero (minus 3 3)", True),
+        ];
+        for (input, value) in cases {
+            assert_eq!(typing(parse(input)), if value == True || value == False { Type::Bool } else { Type::Nat });
+            assert_eq!(eval(parse(input)), value, "{}", input);
+            assert_eq!(big_eval(parse(input)).ok(), Some(value), "{}", input);
+            assert_eq!(check(&parse(input)).map(|_| ()), Ok(()));
+        }
+        assert_eq!(eval(parse("succ 4")), Succ(Box::new(Num(4))));
+
+        // A natural beyond `u64` leaves the primitive stuck rather than
+        // wrapping or saturating.
+        let stuck = [
+            "plus 18446744073709551615 1",
+            "times 4294967296 4294967296",
+            "eq (succ 18446744073709551615) 18446744073709551615",
+            "iszero (plus 18446744073709551615 1)",
+            "succ (times 4294967296 4294967296)",
+        ];
+        for input in stuck.iter() {
+            assert_eq!(eval(parse(input)), parse(input), "{}", input);
+            assert!(big_eval(parse(input)).is_err(), "{}", input);
+            assert!(matches!(check(&parse(input)), Err(Counterexample { step: 0, violation: Violation::Progress { .. }, .. })));
+        }
+        assert_eq!(eval(parse("minus 18446744073709551615 (plus 18446744073709551615 0)")), Num(0));
+        assert_eq!(eval(parse("pred (pred (succ 18446744073709551615))")), parse("pred (pred (succ 18446744073709551615))"));
+        assert!(matches!(eval(parse("pred 1001")), Num(1000)));
+        assert!(big_eval(parse("if false then 0")).is_err());
+
+        assert!(type_check(parse("plus true 1")).is_err());
+        assert_eq!(derive(&parse("lt 1 (iszero 0)")).unwrap_err().position, Position::SecondArgument(Prim::Lt));
+        assert_eq!(derive(&parse("times false 1")).unwrap_err().to_string(), "first argument of times has type Bool, expected Nat");
+        assert_eq!(derive(&parse("eq 1 2")).unwrap().rule, Rule::Prim(Prim::Eq));
+        assert_eq!(derive(&parse("eq 1 2000")).unwrap().to_text(), "eq 1 2000 : Bool  [T-Eq]\n  1 : Nat  [T-Succ]\n    0 : Nat  [T-Zero]\n  2000 : Nat  [T-Num]\n");
+    }
+
+    #[test]
+    fn metrics_test() {
+        let t = Pred(Box::new(If(Box::new(False), Box::new(Zero), Some(Box::new(Succ(Box::new(Zero)))))));
+        assert_eq!((size(&t), depth(&t)), (6, 4));
+        assert_eq!(consts(&t).len(), 2);
+        assert!(consts(&t).contains(&False) && consts(&t).contains(&Zero));
+
+        for t in terms(3) {
+            assert!(consts_lemma_holds(&t));
+            assert!(depth(&t) <= 3 && depth(&t) <= size(&t));
+        }
+    }
+
+    #[test]
+    fn rng_test() {
+        let draws = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..4).map(|_| rng.next_u64()).collect::<Vec<u64>>()
+        };
+        assert_eq!(draws(5), draws(5));
+        assert_ne!(draws(5), draws(6));
+        // This seed scrambles to the all-zero state.
+        assert!(draws(0x9e37_79b9_7f4a_7c15).iter().all(|&n| n != 0));
+        assert!(Rng::new(7).below(10) < 10);
+    }
 }
 
 pub mod parser;
 pub mod tyarith;
+pub mod metrics;
+pub mod derivation;
+pub mod soundness;
+pub mod generator;
+pub mod rng;
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+use crate::tyarith::Term;
+
+/// The constants appearing in `t`, TAPL definition 3.3.1.
+pub fn consts(t: &Term) -> HashSet<Term> {
+    match t {
+        Term::True | Term::False | Term::Zero | Term::Num(_) => {
+            let mut result = HashSet::new();
+            result.insert(t.clone());
+            result
+        }
+        Term::Succ(t1) | Term::Pred(t1) | Term::IsZero(t1) => consts(t1),
+        Term::Prim(_, t1, t2) => {
+            let mut result = consts(t1);
+            result.extend(consts(t2));
+            result
+        }
+        Term::If(t1, t2, t3) => {
+            let mut result = consts(t1);
+            result.extend(consts(t2));
+            if let Some(t3) = t3 {
+                result.extend(consts(t3));
+            }
+            result
+        }
+    }
+}
+
+/// The number of nodes in the syntax tree of `t`, TAPL definition 3.3.2.
+pub fn size(t: &Term) -> usize {
+    match t {
+        Term::True | Term::False | Term::Zero | Term::Num(_) => 1,
+        Term::Succ(t1) | Term::Pred(t1) | Term::IsZero(t1) => size(t1) + 1,
+        Term::Prim(_, t1, t2) => size(t1) + size(t2) + 1,
+        Term::If(t1, t2, t3) => size(t1) + size(t2) + t3.as_ref().map_or(0, |t3| size(t3)) + 1,
+    }
+}
+
+/// The number of nodes on the longest root-to-leaf path of `t`, TAPL
+/// definition 3.3.2.
+pub fn depth(t: &Term) -> usize {
+    match t {
+        Term::True | Term::False | Term::Zero | Term::Num(_) => 1,
+        Term::Succ(t1) | Term::Pred(t1) | Term::IsZero(t1) => depth(t1) + 1,
+        Term::Prim(_, t1, t2) => depth(t1).max(depth(t2)) + 1,
+        Term::If(t1, t2, t3) => depth(t1).max(depth(t2)).max(t3.as_ref().map_or(0, |t3| depth(t3))) + 1,
+    }
+}
+
+/// Lemma 3.3.3: a term has no more distinct constants than nodes.
+pub fn consts_lemma_holds(t: &Term) -> bool {
+    consts(t).len() <= size(t)
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
-use crate::tyarith::Term;
+use crate::tyarith::{Prim, Term};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
@@ -14,8 +14,12 @@ pub enum Token {
     Then,
     Else,
 
+    Num(u64),
+    Prim(Prim),
+
     LParen,
     RParen,
+    Semi,
 }
 
 pub fn get_keywords() -> HashMap<String, Token> {
@@ -30,6 +34,11 @@ pub fn get_keywords() -> HashMap<String, Token> {
     result.insert(String::from("succ"), Token::Succ);
     result.insert(String::from("pred"), Token::Pred);
     result.insert(String::from("then"), Token::Then);
+    result.insert(String::from("plus"), Token::Prim(Prim::Plus));
+    result.insert(String::from("times"), Token::Prim(Prim::Times));
+    result.insert(String::from("minus"), Token::Prim(Prim::Minus));
+    result.insert(String::from("eq"), Token::Prim(Prim::Eq));
+    result.insert(String::from("lt"), Token::Prim(Prim::Lt));
     result
 }
 
@@ -84,17 +93,32 @@ impl<T> Lexer<T>
                     self.result.push(out);
                 }
                 '(' => {
-                    //self.result.push(Token::LParen);
+                    self.result.push(Token::LParen);
                     self.next_char();
                 }
                 ')' => {
-                    //self.result.push(Token::RParen);
+                    self.result.push(Token::RParen);
+                    self.next_char();
+                }
+                ';' => {
+                    self.result.push(Token::Semi);
                     self.next_char();
                 }
-                ' ' => {
+                '0'..='9' => {
+                    let mut digits = String::new();
+                    while let Some(c) = self.chr0.filter(|c| c.is_ascii_digit()) {
+                        digits.push(c);
+                        self.next_char();
+                    }
+                    match digits.parse::<u64>() {
+                        Ok(n) => self.result.push(Token::Num(n)),
+                        Err(_) => panic!("Number too large `{}`~~", digits),
+                    }
+                }
+                ' ' | '\t' | '\n' | '\r' => {
                     self.next_char();
                 },
-                _ => {}
+                c => panic!("Unknown character `{}`~~", c),
             }
 
         }
@@ -143,6 +167,16 @@ impl<T> Parser<T>
                 self.next_token();
                 Some(Term::Zero)
             },
+            Some(Token::Num(n)) => {
+                self.next_token();
+                Some(Term::nat(n))
+            }
+            Some(Token::Prim(prim)) => {
+                self.next_token();
+                let term1 = self.expr().unwrap();
+                let term2 = self.expr().unwrap();
+                Some(Term::Prim(prim, Box::new(term1), Box::new(term2)))
+            }
             Some(Token::Succ) => {
                 self.next_token();
                 let tmp = self.expr().unwrap();
@@ -159,6 +193,15 @@ impl<T> Parser<T>
                 self.next_token();
                 Some(Term::IsZero(Box::new(self.expr().unwrap())))
             }
+            Some(Token::LParen) => {
+                self.next_token();
+                let term = self.expr();
+                if self.tok0 != Some(Token::RParen) {
+                    panic!("Expected `)`~~");
+                }
+                self.next_token();
+                term
+            }
             _ => panic!("Err~~~"),
         }
     }
@@ -177,6 +220,33 @@ impl<T> Parser<T>
         return Some(Term::If(Box::new(cond.unwrap()), Box::new(then_expr.unwrap()), None));
     }
 
+    pub fn parse(&mut self) -> Option<Term> {
+        self.expr()
+    }
+
+    pub fn parse_commands(&mut self) -> Vec<Term> {
+        let mut result = vec![];
+        loop {
+            match self.tok0 {
+                None => break,
+                Some(Token::Semi) => {
+                    self.next_token();
+                }
+                _ => {
+                    match self.parse() {
+                        Some(term) => result.push(term),
+                        None => panic!("Err in command~~"),
+                    }
+                    match self.tok0 {
+                        None | Some(Token::Semi) => {}
+                        _ => panic!("Expected `;`~~"),
+                    }
+                }
+            }
+        }
+        result
+    }
+
     pub fn next_token(&mut self) -> Option<Token> {
         let result = self.tok0.clone();
         self.tok0 = self.token.next();
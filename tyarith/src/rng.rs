@@ -0,0 +1,28 @@
+/// xorshift64*: a small seeded source of random numbers, so generated
+/// terms are the same on every run.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves the all-zero state, so the one seed that
+        // scrambles to it is moved off it.
+        let state = seed ^ 0x9e37_79b9_7f4a_7c15;
+        Rng {
+            state: if state == 0 { 0x2545_f491_4f6c_dd1d } else { state },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `0..n`, close enough to uniform for small `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() >> 33) as usize % n
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+use crate::derivation::derive;
+use crate::tyarith::{eval1, is_val, Term, Type};
+
+/// Which half of TAPL's safety theorem a counterexample breaks.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    // Theorem 8.3.2: `term` is well typed, yet neither a value nor able to
+    // take a step.
+    Progress { term: Term },
+    // Theorem 8.3.3: `before` has type `expected` and steps to `after`,
+    // which does not; `found` is `None` if `after` is ill-typed.
+    Preservation { before: Term, after: Term, expected: Type, found: Option<Type> },
+}
+
+/// A term for which evaluation breaks type safety, with the step at which
+/// it does: 0 is the term itself, `n` the term after `n` steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counterexample {
+    pub term: Term,
+    pub step: usize,
+    pub violation: Violation,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` at step {}: ", self.term, self.step)?;
+        match &self.violation {
+            Violation::Progress { term } => write!(f, "progress fails, `{}` is stuck", term),
+            Violation::Preservation { before, after, expected, found } => {
+                write!(f, "preservation fails, `{}` : {} steps to `{}`, ", before, expected, after)?;
+                match found {
+                    Some(ty) => write!(f, "which has type {}", ty),
+                    None => write!(f, "which is ill-typed"),
+                }
+            }
+        }
+    }
+}
+
+/// Evaluates the well-typed `term` one step at a time, checking progress
+/// and preservation at every term on the way. Returns the number of steps
+/// taken to reach a value.
+///
+/// Panics if `term` is ill-typed; the theorems say nothing about it.
+pub fn check(term: &Term) -> Result<usize, Counterexample> {
+    let ty = match derive(term) {
+        Ok(d) => d.ty,
+        Err(err) => panic!("`{}` is ill-typed: {}", term, err),
+    };
+    let counterexample = |step, violation| Counterexample { term: term.clone(), step, violation };
+
+    let mut current = term.clone();
+    let mut step = 0;
+    loop {
+        let next = match eval1(current.clone()) {
+            Ok(next) => next,
+            Err(_) if is_val(&current) => return Ok(step),
+            Err(_) => return Err(counterexample(step, Violation::Progress { term: current })),
+        };
+        step += 1;
+        let found = derive(&next).ok().map(|d| d.ty);
+        if found != Some(ty) {
+            return Err(counterexample(step, Violation::Preservation { before: current, after: next, expected: ty, found }));
+        }
+        current = next;
+    }
+}
@@ -2,8 +2,55 @@ use crate::tyarith::Term::Succ;
 use std::borrow::Borrow;
 use crate::tyarith::EvalError::{NoRule, NoElse};
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use crate::derivation::derive;
 
-#[derive(Clone, PartialEq)]
+/// The primitive operations on naturals. `minus` truncates at 0; `plus`
+/// and `times` get stuck on a result too large for a `u64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Prim {
+    Plus,
+    Times,
+    Minus,
+    Eq,
+    Lt,
+}
+
+impl Prim {
+    pub fn result_type(self) -> Type {
+        match self {
+            Prim::Plus | Prim::Times | Prim::Minus => Type::Nat,
+            Prim::Eq | Prim::Lt => Type::Bool,
+        }
+    }
+
+    // `None` when the result overflows.
+    fn apply(self, n1: u64, n2: u64) -> Option<Term> {
+        match self {
+            Prim::Plus => n1.checked_add(n2).map(Term::nat),
+            Prim::Times => n1.checked_mul(n2).map(Term::nat),
+            Prim::Minus => Some(Term::nat(n1.saturating_sub(n2))),
+            Prim::Eq => Some(if n1 == n2 { Term::True } else { Term::False }),
+            Prim::Lt => Some(if n1 < n2 { Term::True } else { Term::False }),
+        }
+    }
+}
+
+impl fmt::Display for Prim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Prim::Plus => "plus",
+            Prim::Times => "times",
+            Prim::Minus => "minus",
+            Prim::Eq => "eq",
+            Prim::Lt => "lt",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone)]
 pub enum Term {
     True,
     False,
@@ -12,6 +59,68 @@ pub enum Term {
     If(Box<Term>, Box<Term>, Option<Box<Term>>),
     Succ(Box<Term>),
     Pred(Box<Term>),
+    // A natural held as a machine integer rather than a chain of `Succ`s.
+    Num(u64),
+    Prim(Prim, Box<Term>, Box<Term>),
+}
+
+/// Literals up to this value are read as `succ` chains, as TAPL defines
+/// them; larger ones are held as `Num`.
+pub const MAX_CHAIN_LITERAL: u64 = 1000;
+
+impl Term {
+    /// The numeric value `n`, built the way the parser builds it and the
+    /// primitives build their results.
+    pub fn nat(n: u64) -> Term {
+        if n > MAX_CHAIN_LITERAL {
+            return Term::Num(n);
+        }
+        (0..n).fold(Term::Zero, |t, _| Term::Succ(Box::new(t)))
+    }
+}
+
+// Numeric values compare by the natural they stand for, so a `Num` equals
+// the `succ` chain it abbreviates and printing then parsing any term gives
+// back an equal one.
+impl PartialEq for Term {
+    fn eq(&self, other: &Term) -> bool {
+        if let (Some(n1), Some(n2)) = (numeral(self), numeral(other)) {
+            return n1 == n2;
+        }
+        match (self, other) {
+            (Term::True, Term::True) | (Term::False, Term::False) => true,
+            (Term::IsZero(t1), Term::IsZero(u1)) | (Term::Succ(t1), Term::Succ(u1)) | (Term::Pred(t1), Term::Pred(u1)) => t1 == u1,
+            (Term::If(t1, t2, t3), Term::If(u1, u2, u3)) => t1 == u1 && t2 == u2 && t3 == u3,
+            (Term::Prim(p, t1, t2), Term::Prim(q, u1, u2)) => p == q && t1 == u1 && t2 == u2,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Term {}
+
+impl Hash for Term {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if let Some(n) = numeral(self) {
+            n.hash(state);
+            return;
+        }
+        mem::discriminant(self).hash(state);
+        match self {
+            Term::IsZero(t1) | Term::Succ(t1) | Term::Pred(t1) => t1.hash(state),
+            Term::If(t1, t2, t3) => {
+                t1.hash(state);
+                t2.hash(state);
+                t3.hash(state);
+            }
+            Term::Prim(prim, t1, t2) => {
+                prim.hash(state);
+                t1.hash(state);
+                t2.hash(state);
+            }
+            _ => {}
+        }
+    }
 }
 
 impl fmt::Debug for Term {
@@ -19,11 +128,82 @@ impl fmt::Debug for Term {
         match self.clone() {
             Term::True => write!(f, "True"),
             Term::False => write!(f, "False"),
-            Term::If(ref term1, ref term2, ref term3) => write!(f, "(If {:?} {:?} {:?})", term1, term2, term3.as_ref().unwrap()),
+            Term::If(ref term1, ref term2, Some(ref term3)) => write!(f, "(If {:?} {:?} {:?})", term1, term2, term3),
+            Term::If(ref term1, ref term2, None) => write!(f, "(If {:?} {:?})", term1, term2),
             Term::Zero => write!(f, "Zero"),
             Term::Succ(ref term1) => write!(f, "(Succ {:?})", term1),
             Term::Pred(ref term1) => write!(f, "(Pred {:?})", term1),
             Term::IsZero(ref term1) => write!(f, "(IsZero {:?})", term1),
+            Term::Num(n) => write!(f, "{}", n),
+            Term::Prim(prim, ref term1, ref term2) => write!(f, "({:?} {:?} {:?})", prim, term1, term2),
+        }
+    }
+}
+
+// Numeric values are printed as decimal literals. Arguments of `succ`,
+// `pred`, `iszero` and the primitives are parenthesized unless they are constants or
+// literals, and the parts of an `if` when they are `if`s themselves,
+// so an inner `if` never takes an outer `else`.
+fn write_sub(f: &mut fmt::Formatter, t: &Term, wrap: bool) -> fmt::Result {
+    if wrap {
+        write!(f, "({})", t)
+    } else {
+        write!(f, "{}", t)
+    }
+}
+
+fn numeral(t: &Term) -> Option<u64> {
+    match t {
+        Term::Zero => Some(0),
+        Term::Num(n) => Some(*n),
+        Term::Succ(term1) => numeral(term1).and_then(|n| n.checked_add(1)),
+        _ => None,
+    }
+}
+
+/// Prints the concrete syntax the parser reads back as an equal term.
+/// Every numeric value is printed as a literal.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let is_const = |t: &Term| matches!(t, Term::True | Term::False) || numeral(t).is_some();
+        let is_if = |t: &Term| matches!(t, Term::If(_, _, _));
+        if let Some(n) = numeral(self) {
+            return write!(f, "{}", n);
+        }
+        match self {
+            Term::True => write!(f, "true"),
+            Term::False => write!(f, "false"),
+            Term::Zero => write!(f, "0"),
+            Term::Num(n) => write!(f, "{}", n),
+            Term::Succ(term1) => {
+                write!(f, "succ ")?;
+                write_sub(f, term1, !is_const(term1))
+            }
+            Term::Pred(term1) => {
+                write!(f, "pred ")?;
+                write_sub(f, term1, !is_const(term1))
+            }
+            Term::IsZero(term1) => {
+                write!(f, "iszero ")?;
+                write_sub(f, term1, !is_const(term1))
+            }
+            Term::Prim(prim, term1, term2) => {
+                write!(f, "{} ", prim)?;
+                write_sub(f, term1, !is_const(term1))?;
+                write!(f, " ")?;
+                write_sub(f, term2, !is_const(term2))
+            }
+            Term::If(term1, term2, term3) => {
+                write!(f, "if ")?;
+                write_sub(f, term1, is_if(term1))?;
+                write!(f, " then ")?;
+                write_sub(f, term2, is_if(term2))?;
+                if let Some(term3) = term3 {
+                    write!(f, " else ")?;
+                    write_sub(f, term3, is_if(term3))?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -44,11 +224,19 @@ impl fmt::Debug for EvalError {
     }
 }
 
+// A `succ` past `u64::MAX` is stuck rather than a value.
 fn is_numeric_val(t: &Term) -> bool {
-    match t.clone() {
-        Term::Zero => true,
-        Succ(ref term1) => is_numeric_val((*term1).as_ref()),
-        _ => false,
+    nat_value(t).is_some()
+}
+
+// The natural a numeric value stands for, or `None` if it does not fit in
+// a `u64`.
+fn nat_value(nv: &Term) -> Option<u64> {
+    match nv {
+        Term::Zero => Some(0),
+        Term::Num(n) => Some(*n),
+        Succ(term1) => nat_value(term1)?.checked_add(1),
+        _ => None,
     }
 }
 
@@ -58,16 +246,32 @@ pub enum Type {
     Bool,
 }
 
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Nat => write!(f, "Nat"),
+            Type::Bool => write!(f, "Bool"),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 pub enum TyError {
     TypingError,
 }
 
-fn type_check(term: Term) -> Result<Type, TyError> {
+pub fn type_check(term: Term) -> Result<Type, TyError> {
     match term {
         Term::True => Ok(Type::Bool),
         Term::False => Ok(Type::Bool),
         Term::Zero => Ok(Type::Nat),
+        Term::Num(_) => Ok(Type::Nat),
+        Term::Prim(prim, term1, term2) => {
+            match (type_check(*term1), type_check(*term2)) {
+                (Ok(Type::Nat), Ok(Type::Nat)) => Ok(prim.result_type()),
+                _ => Err(TyError::TypingError),
+            }
+        }
         Term::Succ(term) => {
             match type_check(*term) {
                 Ok(Type::Nat) => Ok(Type::Nat),
@@ -107,15 +311,13 @@ fn type_check(term: Term) -> Result<Type, TyError> {
 }
 
 pub fn typing(term: Term) -> Type {
-    match type_check(term.clone()) {
-        Ok(term1) => {
-            term1
-        }
-        Err(_) => panic!("Err!"),
+    match derive(&term) {
+        Ok(derivation) => derivation.ty,
+        Err(err) => panic!("{}", err),
     }
 }
 
-fn eval1(term: Term) -> Result<Term, EvalError> {
+pub fn eval1(term: Term) -> Result<Term, EvalError> {
     match term {
         Term::If(term1, term2, term3) => {
             match *term1 {
@@ -136,37 +338,52 @@ fn eval1(term: Term) -> Result<Term, EvalError> {
             }
         }
         Term::IsZero(term1) => {
-            match *term1 {
-                Term::Zero => {
+            match nat_value(&term1) {
+                Some(0) => {
                     Ok(Term::True)
                 }
-                Term::Succ(term2) if is_numeric_val((*term2).borrow()) => {
+                Some(_) => {
                     Ok(Term::False)
                 }
-                _ => {
+                None => {
                     let tmp_term = Term::IsZero(Box::from(eval1(*term1)?));
                     Ok(tmp_term)
                 }
             }
         }
         Term::Pred(term1) => {
+            if !is_numeric_val(&term1) {
+                let tmp_term = Term::Pred(Box::from(eval1(*term1)?));
+                return Ok(tmp_term);
+            }
             match *term1 {
-                Term::Zero => {
-                    Ok(Term::Zero)
-                }
-                Term::Succ(term2) if is_numeric_val((*term2).borrow())  => {
+                Term::Succ(term2) => {
                     Ok(*term2)
                 }
+                Term::Num(n) => {
+                    Ok(Term::Num(n.saturating_sub(1)))
+                }
                 _ => {
-                    let tmp_term = Term::Pred(Box::from(eval1(*term1)?));
-                    Ok(tmp_term)
+                    Ok(Term::Zero)
                 }
             }
         }
         Term::Succ(term1) => {
-            match *term1 {
-                Term::Pred(term2) => Ok(Term::Succ(Box::from(eval1(Term::Pred(term2)).unwrap()))),
-                _ => Err(EvalError::NoRule(*term1))
+            let tmp_term = Term::Succ(Box::from(eval1(*term1)?));
+            Ok(tmp_term)
+        }
+        Term::Prim(prim, term1, term2) => {
+            if !is_numeric_val(&term1) {
+                Ok(Term::Prim(prim, Box::new(eval1(*term1)?), term2))
+            } else if !is_numeric_val(&term2) {
+                Ok(Term::Prim(prim, term1, Box::new(eval1(*term2)?)))
+            } else {
+                // There is no rule when the result does not fit in a `u64`.
+                let value = match (nat_value(&term1), nat_value(&term2)) {
+                    (Some(n1), Some(n2)) => prim.apply(n1, n2),
+                    _ => None,
+                };
+                value.ok_or(EvalError::NoRule(Term::Prim(prim, term1, term2)))
             }
         }
         term1 => Err(EvalError::NoRule(term1))
@@ -183,7 +400,7 @@ pub fn eval(term: Term) -> Term {
 }
 
 
-fn is_val(t: &Term) -> bool {
+pub(crate) fn is_val(t: &Term) -> bool {
     match t.clone() {
         Term::True | Term::False => true,
         ref t1 if is_numeric_val(t1) => true,
@@ -192,65 +409,65 @@ fn is_val(t: &Term) -> bool {
 }
 
 
-pub fn big_eval(term: Term) -> Term {
+/// The big-step semantics of TAPL exercise 3.5.17. Stuck terms have no
+/// derivation; the error names the subterm where evaluation stops.
+pub fn big_eval(term: Term) -> Result<Term, EvalError> {
     if is_val(term.borrow()) {
-        return term;
+        return Ok(term);
     }
 
     match term {
         Term::If(term1, term2, term3) => {
-            match *term1 {
+            match big_eval(*term1)? {
                 Term::True => {
                     big_eval(*term2)
                 }
                 Term::False => {
-                    if term3 != None {
-                        big_eval(*term3.unwrap())
-                    } else {
-                        panic!("Err~~");
+                    match term3 {
+                        Some(term3) => big_eval(*term3),
+                        None => Err(NoElse(Term::If(Box::new(Term::False), term2, None))),
                     }
                 }
-                _ => {
-                    let tmp_term = Term::If(Box::new(big_eval(*term1)), term2, term3);
-                    big_eval(tmp_term)
-                }
+                term1 => Err(NoRule(Term::If(Box::new(term1), term2, term3))),
             }
         }
         Term::IsZero(term1) => {
-            match *term1 {
-                Term::Zero => {
-                    Term::True
-                }
-                Term::Succ(term2) if is_numeric_val((*term2).borrow()) => {
-                    Term::False
-                }
-                _ => {
-                    let tmp_term = Term::IsZero(Box::from(big_eval(*term1)));
-                    big_eval(tmp_term)
-                }
+            let term1 = big_eval(*term1)?;
+            match nat_value(&term1) {
+                Some(0) => Ok(Term::True),
+                Some(_) => Ok(Term::False),
+                None => Err(NoRule(Term::IsZero(Box::new(term1)))),
             }
         }
         Term::Pred(term1) => {
-            match *term1 {
+            match big_eval(*term1)? {
                 Term::Zero => {
-                    Term::Zero
+                    Ok(Term::Zero)
                 }
-                Term::Succ(term2) if is_numeric_val((*term2).borrow())  => {
-                    big_eval(*term2)
+                Term::Num(n) => {
+                    Ok(Term::Num(n.saturating_sub(1)))
                 }
-                _ => {
-                    let tmp_term = Term::Pred(Box::from(big_eval(*term1)));
-                    big_eval(tmp_term)
+                Term::Succ(term2) => {
+                    Ok(*term2)
                 }
+                term1 => Err(NoRule(Term::Pred(Box::new(term1)))),
             }
         }
         Term::Succ(term1) => {
-            match *term1 {
-                Term::Pred(term2) => Term::Succ(Box::from(big_eval(Term::Pred(term2)))),
-                _ => *term1
+            match Term::Succ(Box::from(big_eval(*term1)?)) {
+                term if is_numeric_val(&term) => Ok(term),
+                term => Err(NoRule(term)),
             }
         }
-        term1 => term1
+        Term::Prim(prim, term1, term2) => {
+            let (term1, term2) = (big_eval(*term1)?, big_eval(*term2)?);
+            let value = match (nat_value(&term1), nat_value(&term2)) {
+                (Some(n1), Some(n2)) => prim.apply(n1, n2),
+                _ => None,
+            };
+            value.ok_or(NoRule(Term::Prim(prim, Box::new(term1), Box::new(term2))))
+        }
+        term1 => Err(NoRule(term1))
     }
 }
 
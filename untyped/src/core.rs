@@ -45,13 +45,13 @@ impl Context {
     }
 }
 
-struct ContextTerm<'a> {
+pub struct ContextTerm<'a> {
     context: &'a Context,
     term: &'a Term,
 }
 
 impl<'a>  ContextTerm<'a> {
-    fn new(ctx: &'a Context, t: &'a Term) -> Self {
+    pub fn new(ctx: &'a Context, t: &'a Term) -> Self {
         ContextTerm {
             context: ctx,
             term: t
@@ -152,20 +152,22 @@ pub enum EvalError {
     NoElse(Term)
 }
 
-fn eval1(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
-    match t.to_owned() {
-        Term::TmApp(term1, term2) if is_val(&term2) => {
-            if let Term::TmAbs(_, t1) = *term1 {
+pub fn eval1(t: &Term, ctx: &Context) -> Result<Term, EvalError> {
+    // Matching on a reference copies only the subterms that are kept, not
+    // the whole term at every level of the recursion.
+    match t {
+        Term::TmApp(term1, term2) if is_val(term2) => {
+            if let Term::TmAbs(_, t1) = term1.as_ref() {
                 Ok(term_subst_top(term2.as_ref(), t1.as_ref()))
             } else {
-                Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2))
+                Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2.clone()))
             }
         }
-        Term::TmApp(term1, term2) if is_val(&term1) => {
-            Ok(Term::TmApp(term1, Box::new(eval1(term2.as_ref(), ctx)?)))
+        Term::TmApp(term1, term2) if is_val(term1) => {
+            Ok(Term::TmApp(term1.clone(), Box::new(eval1(term2.as_ref(), ctx)?)))
         }
         Term::TmApp(term1, term2) => {
-            Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2))
+            Ok(Term::TmApp(Box::new(eval1(term1.as_ref(), ctx)?), term2.clone()))
         }
         _ => Err(EvalError::NoRule(t.to_owned()))
     }
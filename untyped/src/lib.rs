@@ -41,6 +41,20 @@ mod tests {
 
         assert_eq!(format!("{:?}", eval(out.unwrap().borrow(), parser.ctx.borrow()).unwrap()), "(λ y. (y (λ x. x)))");
     }
+
+    #[test]
+    fn commands_test() {
+        let tmp_str = "(λ x. x) (λ y. y);\n(λ x. (x x))\n  (λ x. x);\n";
+
+        let mut lex = Lexer::new(tmp_str.chars());
+        lex.lex_input();
+        let mut parser = Parser::new(lex.result.into_iter());
+        let out = parser.parse_commands();
+
+        assert_eq!(out.len(), 2);
+        assert_eq!(format!("{:?}", eval(out[0].borrow(), parser.ctx.borrow()).unwrap()), "(λ y. y)");
+        assert_eq!(format!("{:?}", eval(out[1].borrow(), parser.ctx.borrow()).unwrap()), "(λ x. x)");
+    }
 }
 
 
@@ -9,6 +9,7 @@ pub enum Token {
     Dot,
     LParen,
     RParen,
+    Semi,
 }
 
 /*
@@ -83,10 +84,14 @@ impl<T> Lexer<T>
                     self.result.push(Token::Dot);
                     self.next_char();
                 }
-                ' ' => {
+                ';' => {
+                    self.result.push(Token::Semi);
+                    self.next_char();
+                }
+                ' ' | '\t' | '\n' | '\r' => {
                     self.next_char();
                 },
-                _ => {}
+                c => panic!("Unknown character `{}`~~", c),
             }
         }
     }
@@ -198,6 +203,29 @@ impl<T> Parser<T>
         self.term()
     }
 
+    pub fn parse_commands(&mut self) -> Vec<Term> {
+        let mut result = vec![];
+        loop {
+            match self.tok0 {
+                None => break,
+                Some(Token::Semi) => {
+                    self.next_token();
+                }
+                _ => {
+                    match self.parse() {
+                        Some(term) => result.push(term),
+                        None => panic!("Err in command~~"),
+                    }
+                    match self.tok0 {
+                        None | Some(Token::Semi) => {}
+                        _ => panic!("Expected `;`~~"),
+                    }
+                }
+            }
+        }
+        result
+    }
+
     fn next_token(&mut self) -> Option<Token> {
         let tmp_token = self.tok0.clone();
         self.tok0 = self.token.next();